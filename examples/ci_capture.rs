@@ -8,6 +8,7 @@ use bevy_xcap::prelude::*;
 
 const OUTPUT_PATH: &str = "./ci_screenshot_test.png";
 const WAIT_FRAMES: u32 = 30;
+const OUTPUT_SIZE: UVec2 = UVec2::new(400, 300);
 
 fn main() {
     App::new()
@@ -70,7 +71,7 @@ fn auto_capture(
 
         let output = OUTPUT_PATH.to_string();
         commands
-            .spawn(NativeScreenshot::window(window_entity))
+            .spawn(NativeScreenshot::window(window_entity).force_dimensions(OUTPUT_SIZE))
             .observe(
                 move |captured: On<NativeScreenshotCaptured>,
                       mut exit_writer: bevy::ecs::message::MessageWriter<AppExit>| {
@@ -89,7 +90,11 @@ fn auto_capture(
 
                             let meta = std::fs::metadata(&output).expect("file should exist");
                             assert!(meta.len() > 0, "Screenshot file is empty");
-                            assert!(c.width > 0 && c.height > 0, "Screenshot has zero dimensions");
+                            assert_eq!(
+                                UVec2::new(c.width, c.height),
+                                OUTPUT_SIZE,
+                                "Screenshot was not forced to {OUTPUT_SIZE}"
+                            );
 
                             info!("[CI] PASS: {}x{}, {} bytes", c.width, c.height, meta.len());
                             exit_writer.write(AppExit::Success);
@@ -0,0 +1,82 @@
+//! Pixel statistics computed on the worker, for assertions that don't need
+//! the image itself.
+
+use bevy::prelude::*;
+
+/// What [`NativeScreenshot::analyze`](crate::NativeScreenshot::analyze)
+/// computes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Analysis {
+    /// Mean of each RGBA channel.
+    AverageColor,
+    /// Per-channel histograms with this many equal-width buckets, clamped to
+    /// 1..=256.
+    Histogram(u16),
+    /// Mean color of the most common 4-bit-per-channel RGB bin.
+    DominantColor,
+}
+
+/// The outcome of an [`Analysis`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnalysisResult {
+    AverageColor([u8; 4]),
+    /// Pixel counts per bucket, for R, G, B and A in that order.
+    Histogram([Vec<u32>; 4]),
+    DominantColor([u8; 4]),
+}
+
+/// Triggered on the screenshot entity instead of
+/// [`NativeScreenshotCaptured`](crate::NativeScreenshotCaptured) for
+/// [`analyze`](crate::NativeScreenshot::analyze) requests.
+#[derive(EntityEvent)]
+pub struct CaptureAnalysis {
+    pub entity: Entity,
+    /// Size of the analyzed area.
+    pub width: u32,
+    pub height: u32,
+    pub result: AnalysisResult,
+}
+
+pub(crate) fn analyze(image: &image::RgbaImage, analysis: Analysis) -> AnalysisResult {
+    match analysis {
+        Analysis::AverageColor => AnalysisResult::AverageColor(mean_color(image.pixels())),
+        Analysis::Histogram(buckets) => {
+            let buckets = usize::from(buckets.clamp(1, 256));
+            let mut histogram: [Vec<u32>; 4] = std::array::from_fn(|_| vec![0; buckets]);
+            for pixel in image.pixels() {
+                for (channel, &value) in histogram.iter_mut().zip(&pixel.0) {
+                    channel[usize::from(value) * buckets / 256] += 1;
+                }
+            }
+            AnalysisResult::Histogram(histogram)
+        }
+        Analysis::DominantColor => {
+            let mut counts = vec![0u32; 1 << 12];
+            for pixel in image.pixels() {
+                counts[rgb_bin(pixel)] += 1;
+            }
+            let dominant = (0..counts.len()).max_by_key(|&i| counts[i]).unwrap_or(0);
+            AnalysisResult::DominantColor(mean_color(
+                image.pixels().filter(|p| rgb_bin(p) == dominant),
+            ))
+        }
+    }
+}
+
+/// Index of the 4-bit-per-channel RGB bin `pixel` falls into.
+fn rgb_bin(pixel: &image::Rgba<u8>) -> usize {
+    let [r, g, b, _] = pixel.0.map(|c| usize::from(c >> 4));
+    (r << 8) | (g << 4) | b
+}
+
+fn mean_color<'a>(pixels: impl Iterator<Item = &'a image::Rgba<u8>>) -> [u8; 4] {
+    let mut sum = [0u64; 4];
+    let mut count = 0u64;
+    for pixel in pixels {
+        for (total, &value) in sum.iter_mut().zip(&pixel.0) {
+            *total += u64::from(value);
+        }
+        count += 1;
+    }
+    sum.map(|total| (total + count / 2).checked_div(count).unwrap_or(0) as u8)
+}
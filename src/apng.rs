@@ -0,0 +1,249 @@
+//! Lossless animated PNG recordings.
+
+use crate::{CaptureFailed, DeliveryMode, NativeScreenshot, NativeScreenshotCaptured};
+use bevy::prelude::*;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Records `frames` captures of `target`, `interval` apart, into a true-color
+/// animated PNG at `path`. Unlike GIF there's no 256-color palette, so
+/// gradients survive.
+///
+/// Each frame is shown for `interval` (clamped to 65.535 s, the APNG delay
+/// limit at millisecond precision). Frames ignore
+/// [`CaptureConfig::min_interval`](crate::CaptureConfig::min_interval), so a
+/// cooldown longer than `interval` doesn't fail the recording.
+/// [`RecordingProgress`] fires after each
+/// frame. The recording entity despawns itself once the file is written, or
+/// as soon as a frame fails to capture, e.g. because the window closed.
+///
+/// ```ignore
+/// commands.spawn(CaptureApng::new(window, 30, Duration::from_millis(100), "ui.png"));
+/// ```
+#[derive(Component)]
+#[require(ApngFrames)]
+pub struct CaptureApng {
+    pub target: Entity,
+    pub frames: u32,
+    pub interval: Duration,
+    pub path: PathBuf,
+    pub size_mismatch: FrameSizePolicy,
+}
+
+impl CaptureApng {
+    /// # Panics
+    ///
+    /// If `frames` is 0.
+    pub fn new(target: Entity, frames: u32, interval: Duration, path: impl Into<PathBuf>) -> Self {
+        assert!(frames > 0, "an APNG needs at least one frame");
+        Self {
+            target,
+            frames,
+            interval,
+            path: path.into(),
+            size_mismatch: FrameSizePolicy::default(),
+        }
+    }
+}
+
+/// What a recording does when a frame's size differs from the others
+/// (e.g. the window was resized mid-recording).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameSizePolicy {
+    /// Give up and write nothing.
+    #[default]
+    Abort,
+    /// Pad every frame with transparent pixels, on the right and bottom, to
+    /// the largest width and height seen.
+    Pad,
+}
+
+/// Triggered on a [`CaptureApng`] entity after each frame lands, e.g. to
+/// drive a progress bar. Frames can land out of order, so `captured` counts
+/// them rather than naming the latest.
+#[derive(EntityEvent)]
+pub struct RecordingProgress {
+    pub entity: Entity,
+    pub captured: u32,
+    pub total: u32,
+}
+
+/// Frames collected so far, indexed by request order so late captures
+/// still land in the right slot.
+#[derive(Component, Default)]
+pub(crate) struct ApngFrames {
+    frames: Vec<Option<image::RgbaImage>>,
+    next_at: Option<Instant>,
+}
+
+pub(crate) fn drive_apng_recordings(
+    mut commands: Commands,
+    mut recordings: Query<(Entity, &CaptureApng, &mut ApngFrames)>,
+) {
+    let now = Instant::now();
+    for (recording, apng, mut state) in &mut recordings {
+        if state.frames.len() as u32 >= apng.frames || state.next_at.is_some_and(|at| now < at) {
+            continue;
+        }
+
+        let index = state.frames.len();
+        state.frames.push(None);
+        state.next_at = Some(now + apng.interval);
+
+        commands
+            .spawn(
+                NativeScreenshot::window(apng.target)
+                    .delivery(DeliveryMode::Observers)
+                    .bypass_cooldown(true),
+            )
+            .observe(
+                move |captured: On<NativeScreenshotCaptured>,
+                      mut commands: Commands,
+                      mut recordings: Query<(&CaptureApng, &mut ApngFrames)>| {
+                    let Ok((apng, mut state)) = recordings.get_mut(recording) else {
+                        return;
+                    };
+                    let Some(frame) = captured.to_rgba_image() else {
+                        error!("[bevy_xcap] APNG recording aborted: malformed frame {index}");
+                        commands.entity(recording).despawn();
+                        return;
+                    };
+                    state.frames[index] = Some(frame);
+
+                    let captured = state.frames.iter().flatten().count() as u32;
+                    let total = apng.frames;
+                    commands
+                        .entity(recording)
+                        .trigger(move |entity| RecordingProgress {
+                            entity,
+                            captured,
+                            total,
+                        });
+                    if captured == total {
+                        let frames = state.frames.drain(..).flatten().collect();
+                        let path = apng.path.clone();
+                        let (interval, policy) = (apng.interval, apng.size_mismatch);
+                        std::thread::spawn(move || {
+                            match write_apng(&path, frames, interval, policy) {
+                                Ok(()) => info!("[bevy_xcap] Saved APNG to {}", path.display()),
+                                Err(e) => error!("[bevy_xcap] Failed to save APNG: {e}"),
+                            }
+                        });
+                        commands.entity(recording).despawn();
+                    }
+                },
+            )
+            .observe(move |failed: On<CaptureFailed>, mut commands: Commands| {
+                error!("[bevy_xcap] APNG recording aborted: {}", failed.error);
+                commands.entity(recording).try_despawn();
+            });
+    }
+}
+
+fn write_apng(
+    path: &std::path::Path,
+    mut frames: Vec<image::RgbaImage>,
+    interval: Duration,
+    policy: FrameSizePolicy,
+) -> Result<(), String> {
+    let width = frames.iter().map(|f| f.width()).max().unwrap_or(0);
+    let height = frames.iter().map(|f| f.height()).max().unwrap_or(0);
+    if frames.iter().any(|f| f.dimensions() != (width, height)) {
+        if policy == FrameSizePolicy::Abort {
+            return Err("frame sizes differ".to_string());
+        }
+        for frame in &mut frames {
+            if frame.dimensions() != (width, height) {
+                let mut padded = image::RgbaImage::new(width, height);
+                image::imageops::replace(&mut padded, frame, 0, 0);
+                *frame = padded;
+            }
+        }
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(|e| e.to_string())?;
+    let delay_ms = interval.as_millis().min(u128::from(u16::MAX)) as u16;
+    encoder
+        .set_frame_delay(delay_ms, 1000)
+        .map_err(|e| e.to_string())?;
+
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    for frame in &frames {
+        writer
+            .write_image_data(frame.as_raw())
+            .map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{run_until, test_app};
+
+    #[test]
+    fn write_apng_pads_mismatched_frames() {
+        let path = std::env::temp_dir().join(format!("bevy_xcap_apng_{}.png", std::process::id()));
+        let frames = vec![image::RgbaImage::new(4, 2), image::RgbaImage::new(2, 3)];
+        write_apng(
+            &path,
+            frames.clone(),
+            Duration::ZERO,
+            FrameSizePolicy::Abort,
+        )
+        .unwrap_err();
+        write_apng(
+            &path,
+            frames,
+            Duration::from_millis(50),
+            FrameSizePolicy::Pad,
+        )
+        .unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let reader = png::Decoder::new(std::io::Cursor::new(bytes))
+            .read_info()
+            .unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (4, 3));
+        assert_eq!(info.animation_control.unwrap().num_frames, 2);
+    }
+
+    #[test]
+    fn recording_reports_progress_then_despawns() {
+        #[derive(Resource, Default)]
+        struct Progress(Vec<(u32, u32)>);
+
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        app.init_resource::<Progress>();
+        let window = app.world_mut().spawn(Window::default()).id();
+        let path = std::env::temp_dir().join(format!("bevy_xcap_rec_{}.png", std::process::id()));
+        let recording = app
+            .world_mut()
+            .spawn(CaptureApng::new(window, 3, Duration::ZERO, path))
+            .observe(
+                |progress: On<RecordingProgress>, mut seen: ResMut<Progress>| {
+                    seen.0.push((progress.captured, progress.total));
+                },
+            )
+            .id();
+        run_until(&mut app, |world| world.get_entity(recording).is_err());
+
+        assert_eq!(
+            app.world().resource::<Progress>().0,
+            [(1, 3), (2, 3), (3, 3)]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Saving through Bevy's asset I/O instead of `std::fs`.
+
+use crate::NativeScreenshotCaptured;
+use crate::save::encode;
+use bevy::asset::AssetPath;
+use bevy::prelude::*;
+use bevy::tasks::IoTaskPool;
+use image::ImageFormat;
+
+/// Observer callback that encodes captured pixels and writes them through
+/// the [`AssetSource`](bevy::asset::io::AssetSource) behind `path`, so apps
+/// that route file access through custom sources (a virtual filesystem, a
+/// remote store...) get captures there too.
+///
+/// `path` may name a source, e.g. `"captures://latest.png"`; without one the
+/// default source is used, which writes below the `assets` folder. The
+/// format follows the extension, falling back to PNG. Encoding and writing
+/// run as a task on the [`IoTaskPool`]. Requires `AssetPlugin`; failures,
+/// including a source that has no writer, are logged.
+pub fn save_to_asset_path(
+    path: impl Into<AssetPath<'static>>,
+) -> impl FnMut(On<NativeScreenshotCaptured>, Option<Res<AssetServer>>) {
+    let path = path.into();
+    let format = ImageFormat::from_path(path.path()).unwrap_or(ImageFormat::Png);
+    move |captured: On<NativeScreenshotCaptured>, server: Option<Res<AssetServer>>| {
+        let Some(server) = server else {
+            error!("[bevy_xcap] save_to_asset_path requires AssetPlugin");
+            return;
+        };
+        let Some(buffer) = captured.to_rgba_image() else {
+            error!("[bevy_xcap] Capture buffer doesn't match its dimensions");
+            return;
+        };
+        let (server, path) = (server.clone(), path.clone());
+
+        IoTaskPool::get()
+            .spawn(async move {
+                let (width, height) = buffer.dimensions();
+                let written = async {
+                    let bytes = encode(buffer, format).map_err(|e| e.to_string())?;
+                    let source = server
+                        .get_source(path.source().clone())
+                        .map_err(|e| e.to_string())?;
+                    let writer = source.writer().map_err(|e| e.to_string())?;
+                    writer
+                        .write_bytes(path.path(), &bytes)
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+                .await;
+                match written {
+                    Ok(()) => info!("[bevy_xcap] Saved {width}x{height} screenshot to {path}"),
+                    Err(e) => error!("[bevy_xcap] Failed to save screenshot to {path}: {e}"),
+                }
+            })
+            .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NativeScreenshot;
+    use crate::tests::{run_until, spawn_recorded, test_app};
+
+    #[test]
+    fn save_to_asset_path_writes_through_the_default_source() {
+        let dir = std::env::temp_dir().join(format!("bevy_xcap_asset_{}", std::process::id()));
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        app.add_plugins(AssetPlugin {
+            file_path: dir.to_string_lossy().into_owned(),
+            ..default()
+        });
+        let window = app.world_mut().spawn(Window::default()).id();
+        let screenshot = spawn_recorded(&mut app, NativeScreenshot::window(window));
+        app.world_mut()
+            .entity_mut(screenshot)
+            .observe(save_to_asset_path("shots/latest.png"));
+
+        let path = dir.join("shots/latest.png");
+        run_until(&mut app, |_| image::open(&path).is_ok());
+        let saved = image::open(&path).map(|image| (image.width(), image.height()));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(saved.unwrap(), (8, 8));
+    }
+}
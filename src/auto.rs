@@ -0,0 +1,105 @@
+//! Drop-in "press a key to screenshot" utility.
+
+use crate::{DeliveryMode, NativeScreenshot, save_to_disk};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use std::path::PathBuf;
+
+/// Insert this resource to capture a window whenever `key` is pressed.
+///
+/// Screenshots are saved as `screenshot_<n>.png` in `dir`, skipping indices
+/// that already exist so earlier runs aren't overwritten. Nothing happens
+/// while the resource is absent.
+///
+/// ```ignore
+/// app.insert_resource(AutoScreenshot {
+///     key: KeyCode::F12,
+///     dir: "screenshots".into(),
+///     target: AutoTarget::PrimaryWindow,
+/// });
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct AutoScreenshot {
+    pub key: KeyCode,
+    pub dir: PathBuf,
+    pub target: AutoTarget,
+}
+
+/// Which window [`AutoScreenshot`] captures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AutoTarget {
+    #[default]
+    PrimaryWindow,
+    /// The window that currently has focus, if any.
+    Focused,
+    Window(Entity),
+}
+
+pub(crate) fn auto_screenshot(
+    mut commands: Commands,
+    settings: Res<AutoScreenshot>,
+    input: Option<Res<ButtonInput<KeyCode>>>,
+    primary: Query<Entity, With<PrimaryWindow>>,
+    windows: Query<(Entity, &Window)>,
+    mut next_index: Local<u32>,
+) {
+    if !input.is_some_and(|input| input.just_pressed(settings.key)) {
+        return;
+    }
+
+    let target = match settings.target {
+        AutoTarget::PrimaryWindow => primary.single().ok(),
+        AutoTarget::Focused => windows.iter().find(|(_, w)| w.focused).map(|(e, _)| e),
+        AutoTarget::Window(entity) => Some(entity),
+    };
+    let Some(target) = target else {
+        warn!("[bevy_xcap] AutoScreenshot: no window matches {:?}", settings.target);
+        return;
+    };
+
+    let path = loop {
+        let path = settings.dir.join(format!("screenshot_{}.png", *next_index));
+        *next_index += 1;
+        if !path.exists() {
+            break path;
+        }
+    };
+
+    commands
+        .spawn(NativeScreenshot::window(target).delivery(DeliveryMode::Observers))
+        .observe(save_to_disk(path));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{run_until, test_app};
+
+    #[test]
+    fn key_press_saves_the_next_free_index() {
+        let dir = std::env::temp_dir().join(format!("bevy_xcap_auto_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("screenshot_0.png"), []).unwrap();
+
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        app.insert_resource(AutoScreenshot {
+            key: KeyCode::F12,
+            dir: dir.clone(),
+            target: AutoTarget::Window(window),
+        });
+        let mut input = ButtonInput::<KeyCode>::default();
+        input.press(KeyCode::F12);
+        app.insert_resource(input);
+        app.update();
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .clear();
+
+        let path = dir.join("screenshot_1.png");
+        run_until(&mut app, |_| path.exists());
+        let saved = image::open(&path).map(|image| image.width());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(saved.unwrap(), 8);
+    }
+}
@@ -0,0 +1,221 @@
+//! Capturing every window of the app in one go.
+
+use crate::{
+    CaptureError, CaptureFailed, DeliveryMode, NativeScreenshot, NativeScreenshotCaptured,
+    save_to_disk,
+};
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+/// Triggered on the entity returned by
+/// [`screenshot_all_windows`](crate::CaptureCommandsExt::screenshot_all_windows)
+/// on the update after every capture of the batch has finished, successfully
+/// or not. The entity despawns right after.
+#[derive(EntityEvent)]
+pub struct CaptureBatchComplete {
+    pub entity: Entity,
+    /// One PNG per captured window. Saving happens as in [`save_to_disk`], so
+    /// a failed write is logged rather than reported here.
+    pub saved: Vec<PathBuf>,
+    /// Windows whose capture failed.
+    pub failed: Vec<(Entity, CaptureError)>,
+}
+
+/// Progress of a batch, on its entity until it completes.
+#[derive(Component)]
+pub(crate) struct CaptureBatch {
+    pending: usize,
+    saved: Vec<PathBuf>,
+    failed: Vec<(Entity, CaptureError)>,
+}
+
+pub(crate) fn spawn_window_batch(world: &mut World, batch: Entity, dir: PathBuf) {
+    let windows: Vec<(Entity, String)> = world
+        .query::<(Entity, &Window)>()
+        .iter(world)
+        .map(|(entity, window)| (entity, window.title.clone()))
+        .collect();
+    let Ok(mut entity) = world.get_entity_mut(batch) else {
+        return;
+    };
+    entity.insert(CaptureBatch {
+        pending: windows.len(),
+        saved: Vec::new(),
+        failed: Vec::new(),
+    });
+
+    for (&(window, _), stem) in windows.iter().zip(file_stems(&windows)) {
+        let path = dir.join(format!("{stem}.png"));
+        world
+            .spawn(
+                NativeScreenshot::window(window)
+                    .bypass_cooldown(true)
+                    .delivery(DeliveryMode::Observers),
+            )
+            .observe(save_to_disk(path.clone()))
+            .observe(
+                move |_: On<NativeScreenshotCaptured>, mut batches: Query<&mut CaptureBatch>| {
+                    record(&mut batches, batch, Ok(path.clone()));
+                },
+            )
+            .observe(
+                move |failed: On<CaptureFailed>, mut batches: Query<&mut CaptureBatch>| {
+                    record(&mut batches, batch, Err((window, failed.error.clone())));
+                },
+            );
+    }
+}
+
+fn record(
+    batches: &mut Query<&mut CaptureBatch>,
+    batch: Entity,
+    outcome: Result<PathBuf, (Entity, CaptureError)>,
+) {
+    let Ok(mut state) = batches.get_mut(batch) else {
+        return;
+    };
+    match outcome {
+        Ok(path) => state.saved.push(path),
+        Err(failure) => state.failed.push(failure),
+    }
+    state.pending -= 1;
+}
+
+/// Completes batches with nothing left pending, including empty ones, so the
+/// event always arrives after the caller had a chance to observe the entity.
+pub(crate) fn complete_batches(
+    mut commands: Commands,
+    mut batches: Query<(Entity, &mut CaptureBatch)>,
+) {
+    for (batch, mut state) in &mut batches {
+        if state.pending > 0 {
+            continue;
+        }
+        let saved = std::mem::take(&mut state.saved);
+        let failed = std::mem::take(&mut state.failed);
+        commands
+            .entity(batch)
+            .trigger(move |entity| CaptureBatchComplete {
+                entity,
+                saved,
+                failed,
+            })
+            .despawn();
+    }
+}
+
+/// One file stem per window: its title with anything but ASCII letters,
+/// digits, `-` and `_` replaced by `_`, suffixed with the entity index when
+/// the result is empty or shared with another window.
+fn file_stems(windows: &[(Entity, String)]) -> Vec<String> {
+    let sanitized: Vec<String> = windows
+        .iter()
+        .map(|(_, title)| {
+            let stem: String = title
+                .chars()
+                .map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+                    _ => '_',
+                })
+                .collect();
+            stem.trim_matches('_').to_string()
+        })
+        .collect();
+
+    sanitized
+        .iter()
+        .zip(windows)
+        .map(|(stem, (entity, _))| {
+            if stem.is_empty() {
+                format!("window_{}", entity.index())
+            } else if sanitized.iter().filter(|other| *other == stem).count() > 1 {
+                format!("{stem}_{}", entity.index())
+            } else {
+                stem.clone()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CaptureCommandsExt;
+    use crate::tests::{run_until, test_app};
+
+    #[derive(Resource, Default)]
+    struct Completed(Vec<(Vec<PathBuf>, usize)>);
+
+    fn spawn_batch(app: &mut App, dir: PathBuf) -> Entity {
+        app.init_resource::<Completed>();
+        let batch = app
+            .world_mut()
+            .commands()
+            .screenshot_all_windows(dir)
+            .observe(
+                |complete: On<CaptureBatchComplete>, mut completed: ResMut<Completed>| {
+                    let complete = &*complete;
+                    completed
+                        .0
+                        .push((complete.saved.clone(), complete.failed.len()));
+                },
+            )
+            .id();
+        app.world_mut().flush();
+        batch
+    }
+
+    #[test]
+    fn file_stems_sanitize_and_disambiguate_titles() {
+        let windows = [
+            (
+                Entity::from_raw_u32(1).unwrap(),
+                "My Game: Editor".to_string(),
+            ),
+            (Entity::from_raw_u32(2).unwrap(), "Tool".to_string()),
+            (Entity::from_raw_u32(3).unwrap(), "Tool".to_string()),
+            (Entity::from_raw_u32(4).unwrap(), "???".to_string()),
+        ];
+        assert_eq!(
+            file_stems(&windows),
+            ["My_Game__Editor", "Tool_2", "Tool_3", "window_4"]
+        );
+    }
+
+    #[test]
+    fn empty_batch_completes_on_the_next_update() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        let batch = spawn_batch(&mut app, std::env::temp_dir());
+        assert!(app.world().resource::<Completed>().0.is_empty());
+
+        app.update();
+        assert_eq!(app.world().resource::<Completed>().0, [(Vec::new(), 0)]);
+        assert!(app.world().get_entity(batch).is_err());
+    }
+
+    #[test]
+    fn batch_saves_every_window() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        for title in ["main", "tools"] {
+            app.world_mut().spawn(Window {
+                title: title.to_string(),
+                ..default()
+            });
+        }
+        let dir = std::env::temp_dir().join(format!("bevy_xcap_batch_{}", std::process::id()));
+        let batch = spawn_batch(&mut app, dir.clone());
+        run_until(&mut app, |world| world.get_entity(batch).is_err());
+
+        let mut completed = std::mem::take(&mut app.world_mut().resource_mut::<Completed>().0);
+        let present = completed
+            .iter()
+            .flat_map(|(saved, _)| saved)
+            .all(|path| path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+        let (mut saved, failed) = completed.pop().unwrap();
+        saved.sort();
+        assert!(completed.is_empty() && failed == 0);
+        assert_eq!(saved, [dir.join("main.png"), dir.join("tools.png")]);
+        assert!(present);
+    }
+}
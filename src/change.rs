@@ -0,0 +1,128 @@
+//! Waiting for a window's content to change, or to settle, before delivering
+//! a capture.
+
+use crate::{
+    CaptureConfig, CaptureError, CaptureJob, CaptureWarning, CapturedPixels,
+    NativeScreenshotCaptured, diff_images, run_capture,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Settings for [`NativeScreenshot::until_changed`](crate::NativeScreenshot::until_changed).
+#[derive(Clone, Debug)]
+pub struct UntilChanged {
+    pub baseline: ChangeBaseline,
+    /// How long to keep trying before failing with [`CaptureError::Timeout`].
+    pub timeout: Duration,
+    /// Pause between attempts.
+    pub poll_interval: Duration,
+}
+
+/// What a capture must differ from to count as changed.
+///
+/// Captures are compared after all post-processing, so build the baseline
+/// from a capture requested with the same options.
+#[derive(Clone, Debug)]
+pub enum ChangeBaseline {
+    /// Changed once the mean per-channel difference, as in
+    /// [`WindowDiff::mean_diff`](crate::WindowDiff::mean_diff), exceeds
+    /// `min_mean_diff`. A different size always counts as changed.
+    Pixels {
+        image: Arc<image::RgbaImage>,
+        min_mean_diff: f32,
+    },
+    /// Changed once more than `min_distance` of the 64 bits of an 8x8
+    /// average hash differ. Cheaper to keep around than pixels, and blind to
+    /// small details like a blinking cursor.
+    Hash { hash: u64, min_distance: u32 },
+}
+
+impl ChangeBaseline {
+    /// Keeps a copy of `captured`'s pixels. `None` if its buffer doesn't
+    /// match its dimensions.
+    pub fn pixels(captured: &NativeScreenshotCaptured, min_mean_diff: f32) -> Option<Self> {
+        Some(Self::Pixels {
+            image: Arc::new(captured.to_rgba_image()?),
+            min_mean_diff,
+        })
+    }
+
+    /// Keeps only the average hash of `captured`.
+    pub fn hash(captured: &NativeScreenshotCaptured, min_distance: u32) -> Option<Self> {
+        Some(Self::Hash {
+            hash: average_hash(&captured.to_rgba_image()?),
+            min_distance,
+        })
+    }
+
+    fn changed(&self, image: &image::RgbaImage) -> bool {
+        match self {
+            Self::Pixels {
+                image: baseline,
+                min_mean_diff,
+            } => diff_images(baseline, image).map_or(true, |diff| diff.mean > *min_mean_diff),
+            Self::Hash { hash, min_distance } => {
+                (hash ^ average_hash(image)).count_ones() > *min_distance
+            }
+        }
+    }
+}
+
+/// One bit per cell of an 8x8 grayscale thumbnail: set if the cell is
+/// brighter than the mean.
+fn average_hash(image: &image::RgbaImage) -> u64 {
+    let gray = image::imageops::grayscale(image);
+    let small = image::imageops::resize(&gray, 8, 8, image::imageops::FilterType::Triangle);
+    let mean = small.pixels().map(|p| u32::from(p.0[0])).sum::<u32>() / 64;
+    small
+        .pixels()
+        .enumerate()
+        .filter(|(_, p)| u32::from(p.0[0]) > mean)
+        .fold(0, |hash, (i, _)| hash | 1 << i)
+}
+
+/// Captures repeatedly until the result differs from the baseline.
+pub(crate) fn capture_until_changed(
+    job: &CaptureJob,
+    until: &UntilChanged,
+    config: &CaptureConfig,
+) -> Result<CapturedPixels, CaptureError> {
+    let deadline = Instant::now() + until.timeout;
+    loop {
+        let pixels = capture_frame(job, config)?;
+        if until.baseline.changed(&pixels.image) {
+            return Ok(pixels);
+        }
+        if Instant::now() + until.poll_interval > deadline {
+            return Err(CaptureError::Timeout(until.timeout));
+        }
+        std::thread::sleep(until.poll_interval);
+    }
+}
+
+/// One capture, retaken for [`NativeScreenshot::stable`](crate::NativeScreenshot::stable)
+/// jobs until two in a row agree.
+pub(crate) fn capture_frame(
+    job: &CaptureJob,
+    config: &CaptureConfig,
+) -> Result<CapturedPixels, CaptureError> {
+    let mut previous = run_capture(job, config)?;
+    if !job.stable {
+        return Ok(previous);
+    }
+    let policy = config.stable_retry;
+    for retry in 0..=policy.max_retries {
+        std::thread::sleep(policy.delay(retry));
+        let current = run_capture(job, config)?;
+        let settled = diff_images(&previous.image, &current.image)
+            .is_ok_and(|diff| diff.mean <= config.stable_threshold);
+        previous = current;
+        if settled {
+            return Ok(previous);
+        }
+    }
+    previous.warnings.push(CaptureWarning::Unstable {
+        attempts: policy.max_retries + 2,
+    });
+    Ok(previous)
+}
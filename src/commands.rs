@@ -0,0 +1,134 @@
+//! [`Commands`] extensions for managing captures.
+
+use crate::batch::CaptureBatch;
+use crate::region::EmptySelection;
+use crate::{
+    CaptureTimeline, Captured, DecorationMode, NativeScreenshot, RegionSelection, RequestOrder,
+};
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+pub trait CaptureCommandsExt {
+    /// Despawns every [`NativeScreenshot`] entity, pending or in flight,
+    /// along with the batches, recordings, timelines and comparisons that
+    /// would otherwise wait for them or keep requesting more.
+    ///
+    /// Worker threads that are already running finish in the background, but
+    /// their results are discarded: no [`NativeScreenshotCaptured`] or
+    /// [`CaptureFailed`] fires for a cancelled capture, and no
+    /// [`CaptureBatchComplete`] for a cancelled batch.
+    ///
+    /// [`NativeScreenshotCaptured`]: crate::NativeScreenshotCaptured
+    /// [`CaptureFailed`]: crate::CaptureFailed
+    /// [`CaptureBatchComplete`]: crate::CaptureBatchComplete
+    fn cancel_all_captures(&mut self);
+
+    /// Inserts a fresh [`RegionSelection`] for `window`, for the app to
+    /// update while the user drags.
+    fn begin_region_selection(&mut self, window: Entity);
+
+    /// Removes the [`RegionSelection`] and captures the selected part of its
+    /// window's client area.
+    ///
+    /// Returns the screenshot entity right away so observers can be attached.
+    /// If no selection is in progress, or it's empty, it fails with
+    /// [`CaptureError::NoRegionSelected`] on the next update instead.
+    ///
+    /// [`CaptureError::NoRegionSelected`]: crate::CaptureError::NoRegionSelected
+    fn finish_region_selection(&mut self) -> EntityCommands<'_>;
+
+    /// Captures every [`Window`] of the app into `dir`, one PNG per window
+    /// named after its title.
+    ///
+    /// Titles are reduced to ASCII letters, digits, `-` and `_`; empty or
+    /// duplicate ones get the window's entity index appended. Returns an
+    /// entity that receives [`CaptureBatchComplete`] on the update after all
+    /// captures have finished, or on the next update if there are no windows;
+    /// windows that don't have a native handle yet are listed as failed. The
+    /// captures ignore [`CaptureConfig::min_interval`] and always deliver to
+    /// observers, regardless of [`CaptureConfig::delivery`].
+    ///
+    /// [`CaptureBatchComplete`]: crate::CaptureBatchComplete
+    /// [`CaptureConfig::min_interval`]: crate::CaptureConfig::min_interval
+    /// [`CaptureConfig::delivery`]: crate::CaptureConfig::delivery
+    fn screenshot_all_windows(&mut self, dir: impl Into<PathBuf>) -> EntityCommands<'_>;
+}
+
+impl CaptureCommandsExt for Commands<'_, '_> {
+    fn cancel_all_captures(&mut self) {
+        self.queue(|world: &mut World| {
+            let screenshots = entities_with::<NativeScreenshot>(world);
+            if !screenshots.is_empty() {
+                debug!("[bevy_xcap] Cancelling {} captures", screenshots.len());
+            }
+            let mut drivers = entities_with::<EmptySelection>(world);
+            drivers.extend(entities_with::<CaptureBatch>(world));
+            drivers.extend(entities_with::<CaptureTimeline>(world));
+            #[cfg(feature = "apng")]
+            drivers.extend(entities_with::<crate::CaptureApng>(world));
+            #[cfg(feature = "render")]
+            drivers.extend(entities_with::<crate::NativeVsRendered>(world));
+            for entity in drivers.into_iter().chain(screenshots) {
+                world.despawn(entity);
+            }
+        });
+    }
+
+    fn begin_region_selection(&mut self, window: Entity) {
+        self.insert_resource(RegionSelection::new(window));
+    }
+
+    fn finish_region_selection(&mut self) -> EntityCommands<'_> {
+        let screenshot = self.spawn_empty().id();
+        self.queue(move |world: &mut World| {
+            let region = world.remove_resource::<RegionSelection>().and_then(|selection| {
+                let scale_factor = world.get::<Window>(selection.window)?.scale_factor();
+                Some((selection.window, selection.physical_rect(scale_factor)?))
+            });
+            let Ok(mut entity) = world.get_entity_mut(screenshot) else {
+                return;
+            };
+            match region {
+                Some((window, rect)) => {
+                    entity.insert(
+                        NativeScreenshot::window(window)
+                            .decorations(DecorationMode::ClientOnly)
+                            .region(rect),
+                    );
+                }
+                None => {
+                    warn!("[bevy_xcap] No region selected; nothing to capture");
+                    entity.insert(EmptySelection);
+                }
+            }
+        });
+        self.entity(screenshot)
+    }
+
+    fn screenshot_all_windows(&mut self, dir: impl Into<PathBuf>) -> EntityCommands<'_> {
+        let batch = self.spawn_empty().id();
+        let dir = dir.into();
+        self.queue(move |world: &mut World| crate::batch::spawn_window_batch(world, batch, dir));
+        self.entity(batch)
+    }
+}
+
+fn entities_with<C: Component>(world: &mut World) -> Vec<Entity> {
+    world
+        .query_filtered::<Entity, With<C>>()
+        .iter(world)
+        .collect()
+}
+
+pub trait CaptureEntityCommandsExt {
+    /// Queues another capture on a [`reusable`](NativeScreenshot::reusable)
+    /// screenshot entity by removing its [`Captured`] marker. The entity
+    /// queues behind requests already pending at the same priority.
+    fn recapture(&mut self) -> &mut Self;
+}
+
+impl CaptureEntityCommandsExt for EntityCommands<'_> {
+    fn recapture(&mut self) -> &mut Self {
+        self.remove::<Captured>().insert(RequestOrder::default())
+    }
+}
@@ -0,0 +1,101 @@
+//! Upload captures straight into a wgpu texture.
+//!
+//! [`RenderPlugin`](bevy::render::RenderPlugin) inserts clones of
+//! [`RenderDevice`] and [`RenderQueue`] into the main world, so the upload can
+//! happen inside a regular observer without touching `Assets<Image>`. The
+//! resulting [`NativeGpuTexture`] lives on the captured window entity in the
+//! main world; [`XCapPlugin`](crate::XCapPlugin) registers an
+//! [`ExtractComponentPlugin`](bevy::render::extract_component::ExtractComponentPlugin)
+//! for it, so render-world systems (bind group creation, custom render graph
+//! nodes) can read it from the render entity of that window after extraction.
+//!
+//! The pixels still originate on the CPU, so this is one `write_texture` per
+//! capture rather than a true zero-copy path.
+
+use crate::{CaptureColorSpace, NativeScreenshot, NativeScreenshotCaptured};
+use bevy::prelude::*;
+use bevy::render::extract_component::ExtractComponent;
+use bevy::render::render_resource::{
+    Extent3d, Texture, TextureDataOrder, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureUsages, TextureView, TextureViewDescriptor,
+};
+use bevy::render::renderer::{RenderDevice, RenderQueue};
+
+/// GPU texture holding the most recent capture of a window.
+///
+/// Inserted on the target window entity by [`to_gpu_texture`] and replaced on
+/// every subsequent capture. The texture is `Rgba8UnormSrgb` (or `Rgba8Unorm`
+/// for [`CaptureColorSpace::Linear`] captures) with
+/// `TEXTURE_BINDING | COPY_SRC | COPY_DST` usage.
+#[derive(Component, Clone, ExtractComponent)]
+pub struct NativeGpuTexture {
+    pub texture: Texture,
+    pub view: TextureView,
+    pub size: UVec2,
+}
+
+/// Observer callback that uploads captured pixels into a [`NativeGpuTexture`].
+///
+/// Requires `RenderPlugin` to be present; logs an error and does nothing
+/// otherwise. Captures of [`CaptureTarget::WindowId`](crate::CaptureTarget)
+/// targets have no window entity to attach to and are skipped.
+#[allow(clippy::type_complexity)]
+pub fn to_gpu_texture() -> impl FnMut(
+    On<NativeScreenshotCaptured>,
+    Commands,
+    Query<&NativeScreenshot>,
+    Option<Res<RenderDevice>>,
+    Option<Res<RenderQueue>>,
+) {
+    move |captured: On<NativeScreenshotCaptured>,
+          mut commands: Commands,
+          screenshots: Query<&NativeScreenshot>,
+          device: Option<Res<RenderDevice>>,
+          queue: Option<Res<RenderQueue>>| {
+        let (Some(device), Some(queue)) = (device, queue) else {
+            error!("[bevy_xcap] to_gpu_texture requires RenderPlugin");
+            return;
+        };
+        let Some(window) = screenshots
+            .get(captured.entity)
+            .ok()
+            .and_then(|screenshot| screenshot.target.entity())
+        else {
+            return;
+        };
+
+        let c = &*captured;
+        let size = Extent3d {
+            width: c.width,
+            height: c.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture_with_data(
+            &queue,
+            &TextureDescriptor {
+                label: Some("bevy_xcap_capture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: match c.color_space {
+                    CaptureColorSpace::Srgb => TextureFormat::Rgba8UnormSrgb,
+                    CaptureColorSpace::Linear => TextureFormat::Rgba8Unorm,
+                },
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_SRC
+                    | TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            TextureDataOrder::LayerMajor,
+            &c.rgba,
+        );
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        commands.entity(window).try_insert(NativeGpuTexture {
+            texture,
+            view,
+            size: UVec2::new(c.width, c.height),
+        });
+    }
+}
@@ -0,0 +1,103 @@
+//! In-memory ring buffer of recent captures, e.g. for crash reports.
+
+use crate::{CaptureColorSpace, CaptureTarget};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::Instant;
+
+/// Keeps the most recent `capacity` captures of each target.
+///
+/// Opt-in: insert it as a resource and `XCapPlugin` records every successful
+/// [`NativeScreenshotCaptured`](crate::NativeScreenshotCaptured) into it.
+/// Each frame is a full RGBA copy, so the cost is roughly
+/// `width * height * 4 * capacity` bytes per target — about 330 MB for ten
+/// 4K frames.
+#[derive(Resource)]
+pub struct CaptureHistory {
+    capacity: usize,
+    frames: HashMap<CaptureTarget, VecDeque<HistoryFrame>>,
+}
+
+/// A capture stored in [`CaptureHistory`].
+#[derive(Clone)]
+pub struct HistoryFrame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    pub color_space: CaptureColorSpace,
+    pub captured_at: Instant,
+}
+
+impl CaptureHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            frames: HashMap::default(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Targets with at least one buffered frame.
+    pub fn targets(&self) -> impl Iterator<Item = CaptureTarget> + '_ {
+        self.frames.keys().copied()
+    }
+
+    /// Buffered frames of `target`, oldest first.
+    pub fn frames(&self, target: impl Into<CaptureTarget>) -> impl Iterator<Item = &HistoryFrame> {
+        self.frames.get(&target.into()).into_iter().flatten()
+    }
+
+    /// Removes and returns the buffered frames of `target`, oldest first.
+    pub fn drain(&mut self, target: impl Into<CaptureTarget>) -> Vec<HistoryFrame> {
+        self.frames
+            .remove(&target.into())
+            .map(Vec::from)
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub(crate) fn push(&mut self, target: CaptureTarget, frame: HistoryFrame) {
+        if self.capacity == 0 {
+            return;
+        }
+        let frames = self.frames.entry(target).or_default();
+        if frames.len() == self.capacity {
+            frames.pop_front();
+        }
+        frames.push_back(frame);
+    }
+
+    /// Writes every buffered frame to `dir` as `<target>_<n>.png`, oldest
+    /// first, creating `dir` if needed. Returns the number of files written.
+    pub fn save_to_dir(&self, dir: impl AsRef<Path>) -> image::ImageResult<usize> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let mut written = 0;
+        for (target, frames) in &self.frames {
+            for (i, frame) in frames.iter().enumerate() {
+                let path = dir.join(format!("{target}_{i}.png"));
+                image::save_buffer(
+                    &path,
+                    &frame.rgba,
+                    frame.width,
+                    frame.height,
+                    image::ColorType::Rgba8,
+                )?;
+                written += 1;
+            }
+        }
+        info!(
+            "[bevy_xcap] Saved {written} history frames to {}",
+            dir.display()
+        );
+        Ok(written)
+    }
+}
@@ -11,28 +11,507 @@
 //!     .spawn(NativeScreenshot::window(window_entity))
 //!     .observe(save_to_disk("screenshot.png"));
 //! ```
+//!
+//! # Features
+//!
+//! - `apng`: [`CaptureApng`] records a window into an animated PNG.
+//! - `asset`: [`save_to_asset_path`] writes captures through Bevy's asset
+//!   I/O, honoring custom asset sources.
+//! - `mmap`: [`save_raw_mmap`] streams raw pixels into a memory-mapped file.
+//! - `render`: [`to_gpu_texture`] uploads captures into a wgpu texture, and
+//!   [`NativeVsRendered`] diffs a native capture against Bevy's own
+//!   screenshot.
+//! - `shm`: [`ShmSink`] hands captures to another process through shared
+//!   memory.
+//! - `sidecar`: [`save_with_sidecar`] writes a JSON metadata file next to the PNG.
+//! - `ui`: [`to_ui_thumbnail`] shows captures in a Bevy UI node.
+//!
+//! # Testing without a display
+//!
+//! Setting `BEVY_XCAP_MOCK=1` before building the app makes [`XCapPlugin`]
+//! skip xcap and answer every request with a deterministic checkerboard,
+//! 64x64 by default or `BEVY_XCAP_MOCK_SIZE=<width>x<height>`. Any entity
+//! with a [`Window`] is a valid target, native handle or not. This is meant
+//! for tests and headless CI only; with the variable unset nothing changes.
+
+mod analysis;
+#[cfg(feature = "apng")]
+mod apng;
+#[cfg(feature = "asset")]
+mod asset;
+mod auto;
+mod batch;
+mod change;
+mod commands;
+#[cfg(feature = "render")]
+mod gpu;
+mod history;
+#[cfg(feature = "mmap")]
+mod raw;
+mod region;
+#[cfg(feature = "render")]
+mod rendered;
+mod save;
+#[cfg(feature = "shm")]
+mod shm;
+mod timeline;
+#[cfg(feature = "ui")]
+mod ui;
+
+pub use analysis::{Analysis, AnalysisResult, CaptureAnalysis};
+pub use auto::{AutoScreenshot, AutoTarget};
+pub use batch::CaptureBatchComplete;
+pub use change::{ChangeBaseline, UntilChanged};
+pub use commands::{CaptureCommandsExt, CaptureEntityCommandsExt};
+pub use history::{CaptureHistory, HistoryFrame};
+pub use region::RegionSelection;
+pub use save::{save_to_disk, save_to_disk_16bit, save_to_writer};
+pub use timeline::{CaptureTimeline, ScheduledCapture};
+
+#[cfg(feature = "apng")]
+pub use apng::{CaptureApng, FrameSizePolicy, RecordingProgress};
+#[cfg(feature = "asset")]
+pub use asset::save_to_asset_path;
+#[cfg(feature = "mmap")]
+pub use raw::{RawCapture, load_capture, load_raw_mmap, reencode, save_raw_mmap};
+#[cfg(feature = "render")]
+pub use gpu::{NativeGpuTexture, to_gpu_texture};
+#[cfg(feature = "render")]
+pub use rendered::{NativeVsRendered, NativeVsRenderedDiff};
+#[cfg(feature = "shm")]
+pub use shm::ShmSink;
+#[cfg(feature = "sidecar")]
+pub use save::save_with_sidecar;
+#[cfg(feature = "ui")]
+pub use ui::{CaptureThumbnail, to_ui_thumbnail};
 
 pub mod prelude {
     pub use crate::{
-        Captured, Capturing, NativeScreenshot, NativeScreenshotCaptured, XCapPlugin, save_to_disk,
+        AmbiguousTitlePolicy, Analysis, AnalysisResult, AutoScreenshot, AutoTarget, Backpressure,
+        CaptureAnalysis, CaptureBatchComplete, CaptureBitDepth, CaptureColorSpace,
+        CaptureCommandsExt, CaptureConfig, CaptureEntityCommandsExt, CaptureError, CaptureFailed,
+        CaptureHistory, CaptureKind, CaptureMetrics, CaptureMode, CaptureResults, CaptureSource,
+        CaptureTarget, CaptureTimeline, CaptureWarning, Captured, CapturedImageData, Capturing,
+        ChangeBaseline, ColorSpaceHandling, DecorationMode, DeliveryMode, MatchKind,
+        NativeScreenshot, NativeScreenshotCaptured, OversizePolicy, PermissionCheck, PixelSampled,
+        RegionSelection, RetryPolicy, ScheduledCapture, TitleMatch, UntilChanged, WindowDiff,
+        WorkerPriority, XCapPlugin, capture_permission_granted, save_to_disk, save_to_disk_16bit,
+        save_to_writer,
     };
+
+    #[cfg(feature = "apng")]
+    pub use crate::{CaptureApng, FrameSizePolicy, RecordingProgress};
+    #[cfg(feature = "asset")]
+    pub use crate::save_to_asset_path;
+    #[cfg(feature = "mmap")]
+    pub use crate::{RawCapture, load_capture, load_raw_mmap, reencode, save_raw_mmap};
+    #[cfg(feature = "render")]
+    pub use crate::{NativeGpuTexture, NativeVsRendered, NativeVsRenderedDiff, to_gpu_texture};
+    #[cfg(feature = "shm")]
+    pub use crate::ShmSink;
+    #[cfg(feature = "sidecar")]
+    pub use crate::save_with_sidecar;
+    #[cfg(feature = "ui")]
+    pub use crate::{CaptureThumbnail, to_ui_thumbnail};
 }
 
+use bevy::asset::RenderAssetUsages;
+use bevy::log::tracing::{field, Span};
+use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
 use bevy::window::RawHandleWrapper;
-use std::sync::{mpsc, Mutex};
+use std::cmp::Reverse;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
 
 #[derive(Component)]
+#[require(RequestOrder)]
 pub struct NativeScreenshot {
-    pub target: Entity,
+    /// What to capture. This used to be the window `Entity`: use
+    /// [`CaptureTarget::entity`] to get it back, or build requests with
+    /// [`window`](Self::window) rather than a struct literal.
+    pub target: CaptureTarget,
+    pub decorations: DecorationMode,
+    pub color_space: ColorSpaceHandling,
+    /// Second window to capture alongside `target`. When set, a
+    /// [`WindowDiff`] is triggered instead of [`NativeScreenshotCaptured`].
+    pub compare: Option<Entity>,
+    /// Exact output size, regardless of the host's DPI. See
+    /// [`force_dimensions`](Self::force_dimensions).
+    pub force_dimensions: Option<UVec2>,
+    /// Upper bound for the longer side. See
+    /// [`max_dimension`](Self::max_dimension).
+    pub max_dimension: Option<NonZeroU32>,
+    /// Hold the request until the target window has focus. See
+    /// [`require_focus`](Self::require_focus).
+    pub require_focus: bool,
+    /// Dispatch order among pending requests: higher goes first, ties are
+    /// served first-in first-out. Only matters when
+    /// [`CaptureConfig::max_concurrent`] holds requests back.
+    pub priority: i32,
+    /// Ignore [`CaptureConfig::min_interval`]. See
+    /// [`bypass_cooldown`](Self::bypass_cooldown).
+    pub bypass_cooldown: bool,
+    /// Keep the entity after the capture completes. See
+    /// [`reusable`](Self::reusable).
+    pub reusable: bool,
+    /// Overrides [`CaptureConfig::delivery`] for this request. See
+    /// [`delivery`](Self::delivery).
+    pub delivery: Option<DeliveryMode>,
+    /// Deliver statistics instead of pixels. See [`analyze`](Self::analyze).
+    pub analysis: Option<Analysis>,
+    /// Deliver the color of this one pixel instead of the image. See
+    /// [`pixel`](Self::pixel).
+    pub pixel: Option<IVec2>,
+    /// Keep capturing until the content changes. See
+    /// [`until_changed`](Self::until_changed).
+    pub until_changed: Option<UntilChanged>,
+    /// Retake the capture until two in a row agree. See
+    /// [`stable`](Self::stable).
+    pub stable: bool,
+    /// Echoed on [`NativeScreenshotCaptured::tag`]. See [`tag`](Self::tag).
+    pub tag: Option<String>,
+    /// Crop to this rect, in physical pixels of the client area. See
+    /// [`region`](Self::region).
+    pub region: Option<URect>,
+    /// Crop to this UI node's on-screen rect, resolved at dispatch. Takes
+    /// precedence over `region`. See [`ui_node`](Self::ui_node).
+    #[cfg(feature = "ui")]
+    pub ui_node: Option<Entity>,
 }
 
 impl NativeScreenshot {
     pub fn window(window: Entity) -> Self {
-        Self { target: window }
+        Self {
+            target: CaptureTarget::Window(window),
+            decorations: DecorationMode::default(),
+            color_space: ColorSpaceHandling::default(),
+            compare: None,
+            force_dimensions: None,
+            max_dimension: None,
+            require_focus: false,
+            priority: 0,
+            bypass_cooldown: false,
+            reusable: false,
+            delivery: None,
+            analysis: None,
+            pixel: None,
+            until_changed: None,
+            stable: false,
+            tag: None,
+            region: None,
+            #[cfg(feature = "ui")]
+            ui_node: None,
+        }
+    }
+
+    /// Captures just the on-screen rect of the UI `node` inside `window`,
+    /// e.g. a single panel.
+    ///
+    /// The rect is read from the node's [`ComputedNode`](bevy::ui::ComputedNode)
+    /// and [`UiGlobalTransform`](bevy::ui::UiGlobalTransform) when the
+    /// request is dispatched. Both are already in physical pixels, so the
+    /// window's scale factor is accounted for. Implies
+    /// [`DecorationMode::ClientOnly`], since UI coordinates start at the
+    /// client area. Fails with [`CaptureError::NodeNotLaidOut`] if the node
+    /// has no computed layout yet.
+    #[cfg(feature = "ui")]
+    pub fn ui_node(window: Entity, node: Entity) -> Self {
+        Self {
+            decorations: DecorationMode::ClientOnly,
+            ui_node: Some(node),
+            ..Self::window(window)
+        }
+    }
+
+    /// Reads the color of the pixel at `position`, in physical pixels from
+    /// the top-left of `window`'s client area, e.g. for an eyedropper, and
+    /// triggers [`PixelSampled`] instead of [`NativeScreenshotCaptured`].
+    /// Fails with [`CaptureError::PixelOutOfBounds`] if `position` lies
+    /// outside the capture.
+    ///
+    /// xcap can only capture whole windows, so the full client area is still
+    /// read, but only four bytes travel back to the main thread.
+    pub fn pixel(window: Entity, position: IVec2) -> Self {
+        Self {
+            decorations: DecorationMode::ClientOnly,
+            pixel: Some(position),
+            ..Self::window(window)
+        }
+    }
+
+    /// Captures the OS window with xcap id `id`, e.g. one the user picked
+    /// from [`xcap::Window::all`], skipping handle and title matching. The
+    /// window doesn't need to belong to this app. Fails with
+    /// [`CaptureError::WindowNotFound`] if it has closed.
+    ///
+    /// Window-specific options that need a Bevy [`Window`] are ignored:
+    /// [`require_focus`](Self::require_focus) and
+    /// [`DecorationMode::ClientOnly`].
+    pub fn window_id(id: u32) -> Self {
+        Self {
+            target: CaptureTarget::WindowId(id),
+            ..Self::window(Entity::PLACEHOLDER)
+        }
+    }
+
+    /// Captures `a` and `b` concurrently and triggers a [`WindowDiff`] once
+    /// both have landed. Fails with [`CaptureError::SizeMismatch`] if the
+    /// captures differ in size.
+    pub fn diff(a: Entity, b: Entity) -> Self {
+        Self {
+            compare: Some(b),
+            ..Self::window(a)
+        }
+    }
+
+    pub fn decorations(mut self, decorations: DecorationMode) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    pub fn color_space(mut self, color_space: ColorSpaceHandling) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Forces the delivered image to exactly `size`, for snapshot tests that
+    /// must not depend on the machine they run on.
+    ///
+    /// A capture larger than `size` is first downscaled to fit, preserving
+    /// aspect ratio, with [`CaptureConfig::resize_filter`]. Whatever space
+    /// remains is padded on the right and bottom with
+    /// [`CaptureConfig::pad_color`].
+    ///
+    /// xcap 0.8 has no scaled or region-limited window capture on any
+    /// platform (`Monitor::capture_region` would also grab whatever overlaps
+    /// the window), so the downscale always runs on the CPU after a
+    /// full-resolution capture.
+    pub fn force_dimensions(mut self, size: UVec2) -> Self {
+        self.force_dimensions = Some(size);
+        self
+    }
+
+    /// Downscales the capture, preserving aspect ratio, so that neither side
+    /// exceeds `max` pixels. Smaller captures are left alone. Runs on the
+    /// worker with [`CaptureConfig::resize_filter`], before
+    /// [`force_dimensions`](Self::force_dimensions). A `max` of 0 removes
+    /// the limit.
+    pub fn max_dimension(mut self, max: u32) -> Self {
+        self.max_dimension = NonZeroU32::new(max);
+        self
+    }
+
+    /// Defers dispatch, frame by frame, until Bevy reports the target window
+    /// as focused. Fails with [`CaptureError::NotFocused`] if focus doesn't
+    /// arrive within [`CaptureConfig::focus_timeout`].
+    pub fn require_focus(mut self, require_focus: bool) -> Self {
+        self.require_focus = require_focus;
+        self
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Dispatches even within [`CaptureConfig::min_interval`] of the last
+    /// capture of the same target, for explicit user requests that shouldn't
+    /// be rate-limited alongside background ones. The capture still resets
+    /// the target's cooldown.
+    pub fn bypass_cooldown(mut self, bypass_cooldown: bool) -> Self {
+        self.bypass_cooldown = bypass_cooldown;
+        self
+    }
+
+    /// Keeps the entity alive after each capture instead of despawning it,
+    /// for polling loops that would otherwise respawn it every frame.
+    ///
+    /// A finished reusable entity keeps its [`NativeScreenshot`] and
+    /// observers and is parked with a [`Captured`] marker. Remove the marker,
+    /// e.g. with [`CaptureEntityCommandsExt::recapture`], to queue the next
+    /// capture.
+    pub fn reusable(mut self, reusable: bool) -> Self {
+        self.reusable = reusable;
+        self
+    }
+
+    /// Delivers this capture with `delivery` instead of
+    /// [`CaptureConfig::delivery`]. Observer-based callbacks such as
+    /// [`save_to_disk`] need [`DeliveryMode::Observers`] or `Both`.
+    pub fn delivery(mut self, delivery: DeliveryMode) -> Self {
+        self.delivery = Some(delivery);
+        self
+    }
+
+    /// Computes `analysis` on the worker, over `region` if given or the
+    /// whole capture otherwise, and triggers [`CaptureAnalysis`] instead of
+    /// [`NativeScreenshotCaptured`]. The pixels never reach the main thread.
+    pub fn analyze(mut self, region: Option<URect>, analysis: Analysis) -> Self {
+        if region.is_some() {
+            self.region = region;
+        }
+        self.analysis = Some(analysis);
+        self
+    }
+
+    /// Captures repeatedly on the worker, every 100 ms, until the result
+    /// differs from `baseline`, and delivers that capture. Fails with
+    /// [`CaptureError::Timeout`] if nothing changes within `timeout`.
+    ///
+    /// Meant for automation: trigger a UI action, then wait for it to show.
+    /// Adjust [`UntilChanged::poll_interval`] through the field if needed.
+    /// Ignored for [`diff`](Self::diff) requests. With
+    /// [`CaptureMode::Immediate`] the main thread waits the whole time.
+    pub fn until_changed(mut self, baseline: ChangeBaseline, timeout: Duration) -> Self {
+        self.until_changed = Some(UntilChanged {
+            baseline,
+            timeout,
+            poll_interval: Duration::from_millis(100),
+        });
+        self
+    }
+
+    /// Captures twice in quick succession and, if the two differ by more
+    /// than [`CaptureConfig::stable_threshold`], keeps capturing until two
+    /// consecutive captures agree, to avoid delivering a frame torn by a
+    /// repaint in progress.
+    ///
+    /// This costs at least one extra capture plus the pause from
+    /// [`CaptureConfig::stable_retry`], and more while the window keeps
+    /// changing. If the retries run out the last capture is delivered anyway,
+    /// with [`CaptureWarning::Unstable`]. Two matching captures make a torn
+    /// frame unlikely, not impossible: a compositor can tear both the same
+    /// way.
+    pub fn stable(mut self, stable: bool) -> Self {
+        self.stable = stable;
+        self
+    }
+
+    /// Attaches a label, e.g. a request id, that comes back on
+    /// [`NativeScreenshotCaptured::tag`] and [`CapturedImageData::tag`].
+    ///
+    /// For richer context, insert your own components on the screenshot
+    /// entity instead: observers run before the entity is despawned, so they
+    /// can still query it. Only [`reusable`](Self::reusable) entities
+    /// outlive the event, e.g. for systems polling [`CaptureResults`].
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Crops the capture to `region`, in physical pixels from the top-left
+    /// of what [`decorations`](Self::decorations) leaves, before
+    /// [`force_dimensions`](Self::force_dimensions) applies. The part outside
+    /// the capture is clipped; fails with [`CaptureError::RegionOutOfBounds`]
+    /// if nothing is left.
+    pub fn region(mut self, region: URect) -> Self {
+        self.region = Some(region);
+        self
+    }
+}
+
+/// What a [`NativeScreenshot`] captures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CaptureTarget {
+    /// A Bevy window entity, matched to its OS window.
+    Window(Entity),
+    /// Any OS window, by its xcap id.
+    WindowId(u32),
+}
+
+impl CaptureTarget {
+    /// The Bevy window entity, if the target is one.
+    pub fn entity(self) -> Option<Entity> {
+        match self {
+            Self::Window(entity) => Some(entity),
+            Self::WindowId(_) => None,
+        }
+    }
+
+    fn kind(self) -> CaptureKind {
+        match self {
+            Self::Window(_) => CaptureKind::Window,
+            Self::WindowId(_) => CaptureKind::External,
+        }
+    }
+}
+
+impl From<Entity> for CaptureTarget {
+    fn from(entity: Entity) -> Self {
+        Self::Window(entity)
+    }
+}
+
+impl fmt::Display for CaptureTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Window(entity) => write!(f, "{entity}"),
+            Self::WindowId(id) => write!(f, "xcap{id}"),
+        }
     }
 }
 
+/// Which part of the OS window ends up in the capture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecorationMode {
+    /// Whatever xcap returns: the decorated frame on macOS and Windows, the
+    /// client area on X11 (the window manager draws decorations in a separate
+    /// frame window that xcap doesn't capture).
+    #[default]
+    Full,
+    /// Only the content area. On Windows the insets come from the HWND's
+    /// window and client rects. On macOS they're derived from the captured
+    /// size versus Bevy's physical window size, assuming a title bar on top
+    /// and nothing else, which toolbars merged into the title bar throw off.
+    /// Elsewhere this is a no-op that warns once.
+    ClientOnly,
+}
+
+/// How captured pixels are encoded before delivery.
+///
+/// OS windows are composited in sRGB, so the raw bytes are sRGB-encoded.
+/// Treating them as linear values (an `Rgba8Unorm` texture, or math in a
+/// linear-space shader) makes them look washed out. Prefer [`AsSrgb`] and an
+/// sRGB texture format, which lets the GPU decode on sampling at full
+/// precision; [`ConvertToLinear`] is for consumers that can only take linear
+/// data, and loses shadow detail since linear values are stored in 8 bits.
+///
+/// [`AsSrgb`]: ColorSpaceHandling::AsSrgb
+/// [`ConvertToLinear`]: ColorSpaceHandling::ConvertToLinear
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpaceHandling {
+    /// Deliver the pixels untouched.
+    #[default]
+    AsSrgb,
+    /// Apply the sRGB transfer function inverse to RGB on the worker thread.
+    /// Alpha is left as-is.
+    ConvertToLinear,
+}
+
+/// Color space of the bytes in [`NativeScreenshotCaptured::rgba`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureColorSpace {
+    Srgb,
+    Linear,
+}
+
+/// Precision of each channel in [`NativeScreenshotCaptured::rgba`].
+///
+/// xcap reads every platform's window pixels as 8-bit RGBA, so this is
+/// always `Eight` for now; deeper sources would add variants. To write a
+/// 16-bit PNG anyway, see [`save_to_disk_16bit`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CaptureBitDepth {
+    /// One byte per channel.
+    #[default]
+    Eight,
+}
+
 #[derive(Component, Default)]
 pub struct Capturing;
 
@@ -45,156 +524,2682 @@ pub struct NativeScreenshotCaptured {
     pub width: u32,
     pub height: u32,
     pub rgba: Vec<u8>,
+    pub color_space: CaptureColorSpace,
+    pub bit_depth: CaptureBitDepth,
+    /// What kind of thing was captured.
+    pub kind: CaptureKind,
+    /// The xcap window that was matched and captured.
+    pub source: CaptureSource,
+    /// Conditions that may make the pixels look off, e.g. a layered window.
+    pub warnings: Vec<CaptureWarning>,
+    /// The request's [`NativeScreenshot::tag`].
+    pub tag: Option<String>,
+}
+
+/// A non-fatal issue noticed while capturing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CaptureWarning {
+    /// The target is a Windows layered window (`WS_EX_LAYERED`), which GDI
+    /// capture doesn't always read back correctly; expect black regions or
+    /// wrong alpha. `alpha_fixed` is set when the capture came back fully
+    /// transparent and was forced opaque.
+    LayeredWindow { alpha_fixed: bool },
+    /// A [`NativeScreenshot::stable`] request never got two consecutive
+    /// captures to agree within `attempts` captures; the last one was
+    /// delivered.
+    Unstable { attempts: u32 },
+}
+
+/// What a capture was taken of, as requested.
+///
+/// Monitor and desktop capture would add variants here.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CaptureKind {
+    /// A Bevy [`Window`] entity. Pixel sizes follow that window's scale factor.
+    Window,
+    /// An OS window outside Bevy, from [`NativeScreenshot::window_id`]. There
+    /// is no Bevy scale factor to relate it to.
+    External,
+    /// Pixels read back from disk by `load_capture` (`mmap` feature). The
+    /// target is a placeholder.
+    File,
+}
+
+/// Which xcap window a capture came from, and how it was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureSource {
+    pub id: Option<u32>,
+    pub title: Option<String>,
+    pub app_name: Option<String>,
+    pub matched_by: MatchKind,
+}
+
+/// The strategy that resolved the target to an xcap window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MatchKind {
+    /// The native window id from the raw handle (Windows, X11).
+    WindowId,
+    /// The title fallback (macOS, or when the id wasn't found).
+    Title,
+    /// Synthesized by the test-only `BEVY_XCAP_MOCK` backend.
+    Mock,
+    /// Read back from a raw capture file by `load_capture`.
+    File,
+}
+
+impl NativeScreenshotCaptured {
+    /// Builds a Bevy [`Image`], picking `Rgba8UnormSrgb` or `Rgba8Unorm` to
+    /// match [`color_space`](Self::color_space). Returns `None` if `rgba`
+    /// doesn't hold `width * height` pixels.
+    pub fn to_image(&self) -> Option<Image> {
+        Some(Image::from_dynamic(
+            self.to_dynamic_image()?,
+            self.color_space == CaptureColorSpace::Srgb,
+            RenderAssetUsages::default(),
+        ))
+    }
+
+    /// Copies the pixels into an [`image::RgbaImage`]. Returns `None` if
+    /// `rgba` doesn't hold `width * height` pixels.
+    pub fn to_rgba_image(&self) -> Option<image::RgbaImage> {
+        image::RgbaImage::from_raw(self.width, self.height, self.rgba.clone())
+    }
+
+    /// Like [`to_rgba_image`](Self::to_rgba_image), as an
+    /// [`image::DynamicImage`] for encoding or format conversion.
+    pub fn to_dynamic_image(&self) -> Option<image::DynamicImage> {
+        self.to_rgba_image().map(image::DynamicImage::ImageRgba8)
+    }
+}
+
+/// Pixel difference between the two windows of a [`NativeScreenshot::diff`].
+#[derive(EntityEvent)]
+pub struct WindowDiff {
+    pub entity: Entity,
+    pub width: u32,
+    pub height: u32,
+    /// Per-channel absolute difference of RGB, with alpha forced opaque so
+    /// the buffer can be viewed directly.
+    pub diff_image: Vec<u8>,
+    /// Mean absolute difference over all RGB channels, `0.0..=255.0`.
+    pub mean_diff: f32,
+    pub max_diff: u8,
 }
 
-/// Observer callback that saves captured pixels to a PNG file.
-pub fn save_to_disk(
-    path: impl Into<std::path::PathBuf>,
-) -> impl FnMut(On<NativeScreenshotCaptured>) {
-    let path = path.into();
-    move |captured: On<NativeScreenshotCaptured>| {
-        let c = &*captured;
-        match image::save_buffer(&path, &c.rgba, c.width, c.height, image::ColorType::Rgba8) {
-            Ok(()) => info!("[bevy_xcap] Saved {}x{} screenshot to {}", c.width, c.height, path.display()),
-            Err(e) => error!("[bevy_xcap] Failed to save screenshot: {e}"),
+/// The color read by a [`NativeScreenshot::pixel`] request.
+#[derive(EntityEvent)]
+pub struct PixelSampled {
+    pub entity: Entity,
+    pub position: IVec2,
+    /// RGBA, in the request's [`ColorSpaceHandling`].
+    pub color: [u8; 4],
+}
+
+/// Triggered on the screenshot entity when a capture fails.
+#[derive(EntityEvent)]
+pub struct CaptureFailed {
+    pub entity: Entity,
+    pub error: CaptureError,
+}
+
+/// Why a native capture failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureError {
+    /// xcap could not enumerate the OS windows.
+    Enumeration(String),
+    /// xcap enumerated zero windows, typically because the compositor isn't
+    /// ready yet right after startup.
+    NoWindowsAvailable,
+    /// No enumerated window matched the target.
+    WindowNotFound,
+    /// The target entity has no native window handle (it's not a window, or
+    /// its window was closed or isn't created yet).
+    NoNativeHandle(Entity),
+    /// Several windows share the target's title and
+    /// [`AmbiguousTitlePolicy::Error`] is configured.
+    AmbiguousTitle { title: String, ids: Vec<u32> },
+    /// xcap found the window but failed to read its pixels.
+    Capture(String),
+    /// Requested within [`CaptureConfig::min_interval`] of the last capture
+    /// of the same target.
+    Cooldown(Duration),
+    /// The two captures of a [`NativeScreenshot::diff`] differ in size.
+    SizeMismatch { a: UVec2, b: UVec2 },
+    /// [`NativeScreenshot::require_focus`] timed out.
+    NotFocused,
+    /// The [`NativeScreenshot::ui_node`] has no computed layout yet.
+    NodeNotLaidOut(Entity),
+    /// The crop region doesn't overlap the capture.
+    RegionOutOfBounds { region: URect, size: UVec2 },
+    /// [`finish_region_selection`](CaptureCommandsExt::finish_region_selection)
+    /// found no selection, or an empty one.
+    NoRegionSelected,
+    /// The [`NativeScreenshot::pixel`] position lies outside the capture.
+    PixelOutOfBounds { position: IVec2, size: UVec2 },
+    /// The window exceeds [`CaptureConfig::max_pixels`] and
+    /// [`OversizePolicy::Refuse`] is configured.
+    TooLarge { size: UVec2, max_pixels: usize },
+    /// [`NativeScreenshot::until_changed`] saw no change within this long,
+    /// or a `NativeVsRendered` comparison didn't complete in time.
+    Timeout(Duration),
+    /// The OS refused to start a worker thread.
+    Spawn(String),
+    /// The result was discarded because the result channel was full, see
+    /// [`CaptureConfig::backpressure`].
+    Dropped,
+    /// A raw capture file couldn't be read, or is corrupt.
+    Load(String),
+    /// Encoding or writing a capture to disk failed.
+    Save(String),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Enumeration(e) => write!(f, "Failed to enumerate windows: {e}"),
+            Self::NoWindowsAvailable => write!(f, "The OS reported no windows"),
+            Self::WindowNotFound => write!(f, "No matching xcap window found"),
+            Self::NoNativeHandle(target) => write!(f, "{target} has no native window handle"),
+            Self::AmbiguousTitle { title, ids } => {
+                write!(f, "{} windows are titled {title:?}: {ids:?}", ids.len())
+            }
+            Self::Capture(e) => write!(f, "Capture failed: {e}"),
+            Self::Cooldown(interval) => write!(f, "Requested within the {interval:?} cooldown"),
+            Self::SizeMismatch { a, b } => {
+                write!(f, "Cannot diff a {}x{} capture against {}x{}", a.x, a.y, b.x, b.y)
+            }
+            Self::NotFocused => write!(f, "Target window never received focus"),
+            Self::NodeNotLaidOut(node) => write!(f, "UI node {node} has no computed layout"),
+            Self::NoRegionSelected => write!(f, "No region was selected"),
+            Self::RegionOutOfBounds { region, size } => write!(
+                f,
+                "Region {:?}..{:?} lies outside the {}x{} capture",
+                region.min, region.max, size.x, size.y
+            ),
+            Self::PixelOutOfBounds { position, size } => write!(
+                f,
+                "Pixel {position} lies outside the {}x{} capture",
+                size.x, size.y
+            ),
+            Self::TooLarge { size, max_pixels } => write!(
+                f,
+                "{}x{} window exceeds the {max_pixels} pixel limit",
+                size.x, size.y
+            ),
+            Self::Timeout(timeout) => write!(f, "Timed out after {timeout:?}"),
+            Self::Spawn(e) => write!(f, "Failed to start capture thread: {e}"),
+            Self::Dropped => write!(f, "Result dropped: capture channel full"),
+            Self::Load(e) => write!(f, "Failed to load raw capture: {e}"),
+            Self::Save(e) => write!(f, "Failed to save capture: {e}"),
         }
     }
 }
 
-type CaptureResult = (Entity, Result<(u32, u32, Vec<u8>), String>);
+impl std::error::Error for CaptureError {}
 
-#[derive(Resource)]
-struct CaptureReceiver(Mutex<mpsc::Receiver<CaptureResult>>);
+/// Plugin-wide capture settings. Insert your own to override the defaults.
+#[derive(Resource, Clone, Debug)]
+pub struct CaptureConfig {
+    /// What to do when the title fallback matches more than one window.
+    pub ambiguous_title: AmbiguousTitlePolicy,
+    /// Which of several same-titled windows to capture, when
+    /// `ambiguous_title` allows it.
+    pub title_match: TitleMatch,
+    /// Minimum time between two captures of the same target. Requests that
+    /// arrive sooner fail with [`CaptureError::Cooldown`] unless they set
+    /// [`NativeScreenshot::bypass_cooldown`].
+    pub min_interval: Option<Duration>,
+    /// Retries for [`CaptureError::NoWindowsAvailable`], on the worker
+    /// thread. `None` fails immediately.
+    pub no_windows_retry: Option<RetryPolicy>,
+    /// Fill for the padding added by [`NativeScreenshot::force_dimensions`].
+    pub pad_color: [u8; 4],
+    /// Filter used whenever a capture is downscaled.
+    pub resize_filter: image::imageops::FilterType,
+    /// How long a [`NativeScreenshot::require_focus`] request waits.
+    pub focus_timeout: Duration,
+    /// Maximum number of captures in flight at once. Further requests wait,
+    /// in [`NativeScreenshot::priority`] order, until a slot frees up.
+    pub max_concurrent: Option<usize>,
+    /// How many finished captures may wait for `poll_captures` at once. Read
+    /// once, when [`XCapPlugin`] is built, so insert the config first.
+    pub result_capacity: usize,
+    /// What a worker does when `result_capacity` results are already waiting.
+    pub backpressure: Backpressure,
+    /// Whether captures are delivered to observers, [`CaptureResults`], or
+    /// both. [`NativeScreenshot::delivery`] overrides it per request; the
+    /// crate's own helpers, such as [`AutoScreenshot`], always use observers.
+    pub delivery: DeliveryMode,
+    /// Whether captures run on worker threads or inline.
+    pub mode: CaptureMode,
+    /// Scheduling priority of the worker threads.
+    pub worker_priority: WorkerPriority,
+    /// Largest window, in pixels, to capture at full size. The window's size
+    /// is checked before capturing; see [`OversizePolicy`] for what happens
+    /// to larger ones.
+    pub max_pixels: Option<usize>,
+    pub oversize: OversizePolicy,
+    /// On [`CaptureError::WindowNotFound`], log what was searched for and
+    /// every enumerated window, to debug matching. Off by default since the
+    /// list can be long.
+    pub debug_matching: bool,
+    /// Whether to check [`capture_permission_granted`] at startup.
+    pub permission_check: PermissionCheck,
+    /// Largest mean per-channel difference, as in [`WindowDiff::mean_diff`],
+    /// at which two consecutive [`NativeScreenshot::stable`] captures count
+    /// as the same frame.
+    pub stable_threshold: f32,
+    /// Pause between the captures of a [`NativeScreenshot::stable`] request,
+    /// and how many more it takes after the first two disagree.
+    pub stable_retry: RetryPolicy,
+}
 
-#[derive(Resource, Clone)]
-struct CaptureSender(mpsc::Sender<CaptureResult>);
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            ambiguous_title: AmbiguousTitlePolicy::default(),
+            title_match: TitleMatch::default(),
+            min_interval: None,
+            no_windows_retry: Some(RetryPolicy::default()),
+            pad_color: [0, 0, 0, 255],
+            resize_filter: image::imageops::FilterType::Triangle,
+            focus_timeout: Duration::from_secs(5),
+            max_concurrent: None,
+            result_capacity: 16,
+            backpressure: Backpressure::default(),
+            delivery: DeliveryMode::default(),
+            mode: CaptureMode::default(),
+            worker_priority: WorkerPriority::default(),
+            max_pixels: None,
+            oversize: OversizePolicy::default(),
+            debug_matching: false,
+            permission_check: PermissionCheck::default(),
+            stable_threshold: 0.5,
+            stable_retry: RetryPolicy {
+                max_retries: 3,
+                backoff: Duration::from_millis(16),
+            },
+        }
+    }
+}
 
-pub struct XCapPlugin;
+/// What [`XCapPlugin`] does at startup when [`capture_permission_granted`]
+/// is false. Skipped with the mock backend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PermissionCheck {
+    /// Nothing. Leave this for interactive apps that let the OS prompt the
+    /// user on the first capture.
+    #[default]
+    Off,
+    /// Log how to grant the permission.
+    Log,
+    /// Log, then write [`AppExit::Error`] so CI fails fast instead of
+    /// collecting blank captures.
+    Exit,
+}
 
-impl Plugin for XCapPlugin {
-    fn build(&self, app: &mut App) {
-        let (tx, rx) = mpsc::channel();
-        app.insert_resource(CaptureSender(tx));
-        app.insert_resource(CaptureReceiver(Mutex::new(rx)));
-        app.add_systems(Update, (dispatch_captures, poll_captures));
+/// How often, and how patiently, a worker retries a transient failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled after each one.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (zero-based).
+    fn delay(&self, retry: u32) -> Duration {
+        self.backoff.saturating_mul(1 << retry.min(16))
     }
 }
 
-/// Dispatches new capture requests to background threads.
-fn dispatch_captures(
-    mut commands: Commands,
-    screenshots: Query<(Entity, &NativeScreenshot), Added<NativeScreenshot>>,
-    handles: Query<&RawHandleWrapper>,
-    windows: Query<&Window>,
-    sender: Res<CaptureSender>,
-) {
-    for (screenshot_entity, screenshot) in &screenshots {
-        let Ok(raw_handle) = handles.get(screenshot.target) else {
-            warn!(
-                "[bevy_xcap] Target entity {:?} has no RawHandleWrapper",
-                screenshot.target
-            );
-            commands.entity(screenshot_entity).despawn();
-            continue;
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 4,
+            backoff: Duration::from_millis(50),
+        }
+    }
+}
+
+/// What a worker does with its result when the result channel is full.
+///
+/// Dropped results fail with [`CaptureError::Dropped`] and are counted in
+/// [`CaptureMetrics::dropped_results`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Wait until `poll_captures` makes room. Nothing is lost, but the worker
+    /// thread, and the frame it holds, stays alive meanwhile.
+    #[default]
+    Block,
+    /// Discard the result that doesn't fit.
+    DropNewest,
+    /// Discard the oldest waiting result to make room.
+    DropOldest,
+}
+
+/// Where the capture itself runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaptureMode {
+    /// On a background thread; the event fires an update or more after the
+    /// request is dispatched.
+    #[default]
+    Background,
+    /// Synchronously inside `dispatch_captures`, so the event fires in the
+    /// same `app.update()` that dispatches the request. Meant for tests: the
+    /// main thread stalls for the whole capture, typically tens of
+    /// milliseconds per window and more for large ones or while
+    /// [`CaptureConfig::no_windows_retry`] backs off. At most
+    /// [`CaptureConfig::result_capacity`] requests run per update.
+    Immediate,
+}
+
+/// Scheduling priority of the `bevy_xcap-capture-<n>` worker threads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WorkerPriority {
+    /// Whatever the OS gives a new thread.
+    #[default]
+    Normal,
+    /// Below the main and render threads, so captures don't cause hitches:
+    /// nice 10 on Linux, `THREAD_PRIORITY_BELOW_NORMAL` on Windows. Other
+    /// platforms ignore it.
+    Low,
+}
+
+/// What happens to windows larger than [`CaptureConfig::max_pixels`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OversizePolicy {
+    /// Fail with [`CaptureError::TooLarge`] without reading any pixels.
+    #[default]
+    Refuse,
+    /// Capture, then downscale to fit. xcap can only capture at full size,
+    /// so this bounds what's delivered, not the worker's peak memory.
+    Downscale,
+}
+
+/// How successful captures reach user code.
+///
+/// Only [`NativeScreenshotCaptured`] is affected; [`WindowDiff`] and
+/// [`CaptureFailed`] are always triggered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeliveryMode {
+    /// Trigger [`NativeScreenshotCaptured`] on the screenshot entity.
+    #[default]
+    Observers,
+    /// Queue the capture in [`CaptureResults`] instead.
+    Poll,
+    /// Both, at the cost of one extra copy of the pixels.
+    Both,
+}
+
+/// Captures queued for systems that poll instead of observing, when
+/// [`CaptureConfig::delivery`] or [`NativeScreenshot::delivery`] is `Poll`
+/// or `Both`.
+///
+/// ```ignore
+/// fn handle(mut results: ResMut<CaptureResults>) {
+///     for capture in results.drain() {
+///         println!("{}x{} from {:?}", capture.width, capture.height, capture.target);
+///     }
+/// }
+/// ```
+///
+/// Nothing is dropped, so drain it regularly.
+#[derive(Resource, Default)]
+pub struct CaptureResults(Vec<CapturedImageData>);
+
+impl CaptureResults {
+    /// Removes and returns the queued captures, oldest first.
+    pub fn drain(&mut self) -> Vec<CapturedImageData> {
+        std::mem::take(&mut self.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A capture queued in [`CaptureResults`]; the same data as
+/// [`NativeScreenshotCaptured`], plus the window it was taken of.
+#[derive(Clone, Debug)]
+pub struct CapturedImageData {
+    /// The screenshot entity, despawned by now unless it's reusable.
+    pub entity: Entity,
+    pub target: CaptureTarget,
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    pub color_space: CaptureColorSpace,
+    pub bit_depth: CaptureBitDepth,
+    pub kind: CaptureKind,
+    pub source: CaptureSource,
+    pub warnings: Vec<CaptureWarning>,
+    pub tag: Option<String>,
+}
+
+/// Counters for the capture pipeline.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct CaptureMetrics {
+    /// Results discarded by [`CaptureConfig::backpressure`].
+    pub dropped_results: u64,
+}
+
+/// Policy for title matches that resolve to several windows.
+///
+/// Either way a warning listing the candidates is logged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AmbiguousTitlePolicy {
+    /// Capture one of them, picked by [`CaptureConfig::title_match`]: the
+    /// first in xcap's enumeration order by default.
+    #[default]
+    UseFirst,
+    /// Fail with [`CaptureError::AmbiguousTitle`].
+    Error,
+}
+
+/// Which window the title fallback picks among several with the same title.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TitleMatch {
+    /// The first in xcap's enumeration order.
+    #[default]
+    FirstFound,
+    /// The frontmost, by xcap's z-order. Windows whose z-order can't be read
+    /// rank behind all others, so this degrades to `FirstFound` when none
+    /// can.
+    TopMost,
+}
+
+/// Root `native_capture` span of a request, kept alive until the screenshot
+/// entity is despawned so the span's lifetime covers the whole pipeline.
+#[derive(Component)]
+struct CaptureSpan(Span);
+
+/// Everything a worker thread needs to serve one request.
+struct CaptureJob {
+    backend: JobBackend,
+    title: Option<String>,
+    client_size: Option<UVec2>,
+    decorations: DecorationMode,
+    color_space: ColorSpaceHandling,
+    force_dimensions: Option<UVec2>,
+    max_dimension: Option<NonZeroU32>,
+    region: Option<URect>,
+    until_changed: Option<UntilChanged>,
+    stable: bool,
+    analysis: Option<Analysis>,
+    pixel: Option<IVec2>,
+}
+
+enum JobBackend {
+    Xcap(RawHandleWrapper),
+    XcapId(u32),
+    /// Checkerboard of this size, see [`MockBackend`].
+    Mock(UVec2),
+}
+
+/// Test-only backend selected by `BEVY_XCAP_MOCK`.
+///
+/// Replaces xcap with a deterministic checkerboard so the pipeline can run
+/// without a display. Targets only need a [`Window`], not a native handle.
+/// [`CaptureConfig::max_pixels`] applies as if the checkerboard were the
+/// window.
+#[derive(Resource)]
+struct MockBackend(UVec2);
+
+impl MockBackend {
+    const DEFAULT_SIZE: UVec2 = UVec2::new(64, 64);
+
+    /// Reads `BEVY_XCAP_MOCK` (anything but empty or `0` enables it) and
+    /// `BEVY_XCAP_MOCK_SIZE` (`<width>x<height>`).
+    fn from_env() -> Option<Self> {
+        let enabled = std::env::var("BEVY_XCAP_MOCK").ok()?;
+        if enabled.is_empty() || enabled == "0" {
+            return None;
+        }
+        let size = match std::env::var("BEVY_XCAP_MOCK_SIZE") {
+            Ok(size) => parse_size(&size).unwrap_or_else(|| {
+                warn!("[bevy_xcap] Ignoring malformed BEVY_XCAP_MOCK_SIZE {size:?}");
+                Self::DEFAULT_SIZE
+            }),
+            Err(_) => Self::DEFAULT_SIZE,
         };
+        Some(Self(size))
+    }
+}
+
+fn parse_size(size: &str) -> Option<UVec2> {
+    let (width, height) = size.split_once('x')?;
+    let size = UVec2::new(width.trim().parse().ok()?, height.trim().parse().ok()?);
+    (size.x > 0 && size.y > 0).then_some(size)
+}
+
+/// Pixels that made it through the worker's post-processing.
+struct CapturedPixels {
+    image: image::RgbaImage,
+    color_space: CaptureColorSpace,
+    source: CaptureSource,
+    warnings: Vec<CaptureWarning>,
+}
+
+/// Result of two captures compared pixel by pixel.
+struct ImageDiff {
+    image: image::RgbaImage,
+    mean: f32,
+    max: u8,
+}
 
-        let window_title = windows
-            .get(screenshot.target)
-            .map(|w| w.title.clone())
-            .ok();
+enum CaptureOutput {
+    Image(CapturedPixels),
+    Diff(ImageDiff),
+    Analysis { size: UVec2, result: AnalysisResult },
+    Pixel { position: IVec2, color: [u8; 4] },
+}
 
-        commands.entity(screenshot_entity).insert(Capturing);
+type CaptureResult = (Entity, Result<CaptureOutput, CaptureError>);
 
-        let raw_handle = raw_handle.clone();
-        let tx = sender.0.clone();
+/// Spawn order of a request, breaking ties between equal priorities.
+#[derive(Component)]
+struct RequestOrder(u64);
 
-        std::thread::spawn(move || {
-            let result = capture_window(&raw_handle, window_title.as_deref());
-            let _ = tx.send((screenshot_entity, result));
-        });
+impl Default for RequestOrder {
+    fn default() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
     }
 }
 
-/// Collects completed captures and triggers entity events.
-fn poll_captures(mut commands: Commands, receiver: Res<CaptureReceiver>) {
-    let rx = receiver.0.lock().unwrap();
-    while let Ok((screenshot_entity, result)) = rx.try_recv() {
-        match result {
-            Ok((width, height, rgba)) => {
-                commands
-                    .entity(screenshot_entity)
-                    .remove::<Capturing>()
-                    .insert(Captured)
-                    .trigger(move |entity| NativeScreenshotCaptured {
-                        entity,
-                        width,
-                        height,
-                        rgba,
-                    });
-                commands.entity(screenshot_entity).despawn();
+/// When a [`NativeScreenshot::require_focus`] request started waiting.
+#[derive(Component)]
+struct AwaitingFocus(Instant);
+
+/// When each target was last dispatched, for [`CaptureConfig::min_interval`].
+#[derive(Resource, Default)]
+struct LastCaptures(HashMap<CaptureTarget, Instant>);
+
+/// How often identical consecutive failures of one target are logged.
+const FAILURE_LOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Last failure logged per target, so a target that fails every frame (e.g.
+/// while its window is dragged) logs once per [`FAILURE_LOG_INTERVAL`] with a
+/// count instead of flooding the console.
+#[derive(Resource, Default)]
+struct FailureLog(HashMap<CaptureTarget, LoggedFailure>);
+
+struct LoggedFailure {
+    error: CaptureError,
+    logged_at: Instant,
+    /// Identical failures since `logged_at` that weren't logged.
+    repeats: u32,
+}
+
+impl FailureLog {
+    /// Logs `error` right away unless it repeats the last failure logged for
+    /// `target` within the interval, in which case it's only counted.
+    fn failed(&mut self, target: CaptureTarget, error: &CaptureError, now: Instant) {
+        if let Some(last) = self.0.get_mut(&target) {
+            if last.error == *error && now.duration_since(last.logged_at) < FAILURE_LOG_INTERVAL {
+                last.repeats += 1;
+                return;
             }
-            Err(e) => {
-                warn!("[bevy_xcap] Failed to capture window: {e}");
-                commands.entity(screenshot_entity).despawn();
+            last.summarize(target, now);
+        }
+        warn!("[bevy_xcap] Failed to capture {target}: {error}");
+        self.0.insert(
+            target,
+            LoggedFailure {
+                error: error.clone(),
+                logged_at: now,
+                repeats: 0,
+            },
+        );
+    }
+
+    /// Logs the counts of repeats older than the interval, and forgets
+    /// targets that have stopped failing.
+    fn flush(&mut self, now: Instant) {
+        self.0.retain(|&target, last| {
+            if now.duration_since(last.logged_at) < FAILURE_LOG_INTERVAL {
+                return true;
             }
+            let repeated = last.repeats > 0;
+            last.summarize(target, now);
+            last.logged_at = now;
+            repeated
+        });
+    }
+}
+
+impl LoggedFailure {
+    fn summarize(&mut self, target: CaptureTarget, now: Instant) {
+        if self.repeats > 0 {
+            warn!(
+                "[bevy_xcap] Capture of {target} failed {} more times in the last {:.1?}: {}",
+                self.repeats,
+                now.duration_since(self.logged_at),
+                self.error
+            );
+            self.repeats = 0;
         }
     }
 }
 
-fn capture_window(
-    raw_handle: &RawHandleWrapper,
-    title: Option<&str>,
-) -> Result<(u32, u32, Vec<u8>), String> {
-    let all_windows =
-        xcap::Window::all().map_err(|e| format!("Failed to enumerate windows: {e}"))?;
+#[derive(Resource)]
+struct CaptureReceiver {
+    rx: Arc<Mutex<mpsc::Receiver<CaptureResult>>>,
+    /// Requests whose results a worker discarded.
+    dropped: Arc<Mutex<Vec<Entity>>>,
+}
+
+#[derive(Resource, Clone)]
+struct CaptureSender {
+    tx: mpsc::SyncSender<CaptureResult>,
+    /// Shared with [`CaptureReceiver`] so `DropOldest` can evict.
+    rx: Arc<Mutex<mpsc::Receiver<CaptureResult>>>,
+    dropped: Arc<Mutex<Vec<Entity>>>,
+}
 
-    let handle = raw_handle.get_window_handle();
+impl CaptureSender {
+    /// Hands a result to `poll_captures`, applying `policy` if the channel
+    /// is full.
+    fn send(&self, mut result: CaptureResult, policy: Backpressure) {
+        loop {
+            let rejected = match policy {
+                Backpressure::Block => {
+                    let _ = self.tx.send(result);
+                    return;
+                }
+                _ => match self.tx.try_send(result) {
+                    Ok(()) | Err(mpsc::TrySendError::Disconnected(_)) => return,
+                    Err(mpsc::TrySendError::Full(rejected)) => rejected,
+                },
+            };
+            if policy == Backpressure::DropNewest {
+                self.dropped.lock().unwrap().push(rejected.0);
+                return;
+            }
 
-    // Match by native window ID (Windows/Linux)
-    if let Some(target_id) = native_window_id(handle) {
-        if let Some(w) = all_windows.iter().find(|w| w.id().ok() == Some(target_id)) {
-            return capture_xcap_window(w);
+            // Evict one and retry. If poll_captures emptied the channel in
+            // the meantime there's nothing to evict and the retry succeeds.
+            let evicted = self.rx.lock().unwrap().try_recv();
+            if let Ok((oldest, _)) = evicted {
+                self.dropped.lock().unwrap().push(oldest);
+            }
+            result = rejected;
         }
     }
+}
 
-    // Fallback: match by title (macOS doesn't expose window IDs via raw handles)
-    if let Some(title) = title {
-        if let Some(w) = all_windows
-            .iter()
-            .find(|w| w.title().ok().as_deref() == Some(title))
-        {
-            return capture_xcap_window(w);
+pub struct XCapPlugin;
+
+impl Plugin for XCapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CaptureConfig>();
+        let capacity = app.world().resource::<CaptureConfig>().result_capacity;
+        let (tx, rx) = mpsc::sync_channel(capacity.max(1));
+        let rx = Arc::new(Mutex::new(rx));
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        app.insert_resource(CaptureSender {
+            tx,
+            rx: rx.clone(),
+            dropped: dropped.clone(),
+        });
+        app.insert_resource(CaptureReceiver { rx, dropped });
+        app.init_resource::<CaptureMetrics>();
+        app.init_resource::<CaptureResults>();
+        app.init_resource::<LastCaptures>();
+        app.init_resource::<FailureLog>();
+        if let Some(mock) = MockBackend::from_env() {
+            warn!(
+                "[bevy_xcap] BEVY_XCAP_MOCK is set: captures are synthetic {}x{} checkerboards",
+                mock.0.x, mock.0.y
+            );
+            app.insert_resource(mock);
         }
-    }
+        app.add_systems(Startup, check_capture_permission);
+        app.add_systems(Update, (dispatch_captures, poll_captures).chain());
+        app.add_systems(Update, region::fail_empty_selections);
+        app.add_systems(
+            Update,
+            auto::auto_screenshot.run_if(resource_exists::<AutoScreenshot>),
+        );
 
-    Err("No matching xcap window found".to_string())
-}
+        app.add_systems(Update, timeline::drive_capture_timelines);
+        app.add_systems(Update, batch::complete_batches);
 
-fn capture_xcap_window(window: &xcap::Window) -> Result<(u32, u32, Vec<u8>), String> {
-    let image = window
-        .capture_image()
-        .map_err(|e| format!("Capture failed: {e}"))?;
+        #[cfg(feature = "apng")]
+        app.add_systems(Update, apng::drive_apng_recordings);
 
-    let width = image.width();
-    let height = image.height();
-    let rgba = image.into_raw();
+        #[cfg(feature = "render")]
+        app.add_systems(Update, rendered::start_rendered_comparisons);
 
-    Ok((width, height, rgba))
+        #[cfg(feature = "render")]
+        app.add_plugins(
+            bevy::render::extract_component::ExtractComponentPlugin::<NativeGpuTexture>::default(),
+        );
+    }
 }
 
-fn native_window_id(handle: raw_window_handle::RawWindowHandle) -> Option<u32> {
-    #[cfg(target_os = "windows")]
-    if let raw_window_handle::RawWindowHandle::Win32(h) = handle {
-        return Some(h.hwnd.get() as u32);
+/// Whether the OS lets this process read other windows' pixels.
+///
+/// On macOS this asks `CGPreflightScreenCaptureAccess` whether Screen
+/// Recording permission is granted, without prompting; it's false both when
+/// the user denied it and when they haven't been asked yet. Other platforms
+/// have no such permission and always return true.
+pub fn capture_permission_granted() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        #[link(name = "CoreGraphics", kind = "framework")]
+        extern "C" {
+            fn CGPreflightScreenCaptureAccess() -> bool;
+        }
+
+        // SAFETY: takes no arguments and only reads the process's TCC state.
+        unsafe { CGPreflightScreenCaptureAccess() }
     }
 
-    #[cfg(target_os = "linux")]
-    match handle {
-        raw_window_handle::RawWindowHandle::Xlib(h) => return Some(h.window as u32),
-        raw_window_handle::RawWindowHandle::Xcb(h) => return Some(h.window.get()),
-        _ => {}
+    #[cfg(not(target_os = "macos"))]
+    true
+}
+
+fn check_capture_permission(
+    config: Res<CaptureConfig>,
+    mock: Option<Res<MockBackend>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    if config.permission_check == PermissionCheck::Off
+        || mock.is_some()
+        || capture_permission_granted()
+    {
+        return;
     }
+    error!(
+        "[bevy_xcap] Screen Recording permission is not granted, so captures will fail or \
+         come back blank. Grant it to this binary, or to the terminal running it, in System \
+         Settings > Privacy & Security > Screen Recording, then restart the process: the \
+         permission only applies to processes started after it's granted."
+    );
+    if config.permission_check == PermissionCheck::Exit {
+        exit.write(AppExit::error());
+    }
+}
 
-    let _ = handle;
-    None
+/// Screenshot entities that haven't been dispatched yet.
+type Pending = (Without<Capturing>, Without<Captured>);
+
+/// Dispatches pending capture requests to background threads, highest
+/// priority first.
+#[allow(clippy::too_many_arguments)]
+fn dispatch_captures(
+    mut commands: Commands,
+    screenshots: Query<
+        (Entity, &NativeScreenshot, &RequestOrder, Option<&AwaitingFocus>),
+        Pending,
+    >,
+    capturing: Query<(), With<Capturing>>,
+    handles: Query<&RawHandleWrapper>,
+    windows: Query<&Window>,
+    sender: Res<CaptureSender>,
+    config: Res<CaptureConfig>,
+    mut last_captures: ResMut<LastCaptures>,
+    mut failure_log: ResMut<FailureLog>,
+    mock: Option<Res<MockBackend>>,
+    #[cfg(feature = "ui")] nodes: Query<(&bevy::ui::ComputedNode, &bevy::ui::UiGlobalTransform)>,
+) {
+    let mock = mock.map(|mock| mock.0);
+    let mut pending: Vec<_> = screenshots.iter().collect();
+    pending.sort_by_key(|(_, screenshot, order, _)| (Reverse(screenshot.priority), order.0));
+    let mut in_flight = capturing.iter().count();
+
+    for (screenshot_entity, screenshot, _, awaiting_focus) in pending {
+        if screenshot.require_focus
+            && screenshot
+                .target
+                .entity()
+                .is_some_and(|window| !windows.get(window).is_ok_and(|w| w.focused))
+        {
+            match awaiting_focus {
+                None => {
+                    commands
+                        .entity(screenshot_entity)
+                        .insert(AwaitingFocus(Instant::now()));
+                }
+                Some(since) if since.0.elapsed() >= config.focus_timeout => {
+                    reject(
+                        &mut commands,
+                        &mut failure_log,
+                        screenshot_entity,
+                        screenshot,
+                        CaptureError::NotFocused,
+                    );
+                }
+                Some(_) => {}
+            }
+            continue;
+        }
+
+        if config.max_concurrent.is_some_and(|max| in_flight >= max) {
+            continue;
+        }
+        // Immediate results wait in the channel until poll_captures runs
+        // later this update; never queue more than fit, or the send blocks.
+        if config.mode == CaptureMode::Immediate && in_flight >= config.result_capacity.max(1) {
+            continue;
+        }
+
+        let span = info_span!(
+            "native_capture",
+            screenshot = ?screenshot_entity,
+            target = ?screenshot.target,
+            compare = ?screenshot.compare,
+        );
+        let _enter = span.enter();
+
+        let job = match prepare_job(screenshot.target, screenshot, &handles, &windows, mock) {
+            Ok(job) => job,
+            Err(error) => {
+                reject(
+                    &mut commands,
+                    &mut failure_log,
+                    screenshot_entity,
+                    screenshot,
+                    error,
+                );
+                continue;
+            }
+        };
+        #[cfg(feature = "ui")]
+        let job = match screenshot.ui_node {
+            None => job,
+            Some(node) => match ui_node_rect(node, &nodes) {
+                Some(rect) => CaptureJob {
+                    region: Some(rect),
+                    ..job
+                },
+                None => {
+                    reject(
+                        &mut commands,
+                        &mut failure_log,
+                        screenshot_entity,
+                        screenshot,
+                        CaptureError::NodeNotLaidOut(node),
+                    );
+                    continue;
+                }
+            },
+        };
+        let compare_job = match screenshot.compare {
+            Some(other) => match prepare_job(other.into(), screenshot, &handles, &windows, mock) {
+                Ok(compare) => Some(CaptureJob {
+                    region: job.region,
+                    ..compare
+                }),
+                Err(error) => {
+                    reject(
+                        &mut commands,
+                        &mut failure_log,
+                        screenshot_entity,
+                        screenshot,
+                        error,
+                    );
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let now = Instant::now();
+        if let (Some(min_interval), Some(last)) =
+            (config.min_interval, last_captures.0.get(&screenshot.target))
+        {
+            if !screenshot.bypass_cooldown && now.duration_since(*last) < min_interval {
+                reject(
+                    &mut commands,
+                    &mut failure_log,
+                    screenshot_entity,
+                    screenshot,
+                    CaptureError::Cooldown(min_interval),
+                );
+                continue;
+            }
+        }
+        last_captures.0.insert(screenshot.target, now);
+
+        commands
+            .entity(screenshot_entity)
+            .remove::<AwaitingFocus>()
+            .insert((Capturing, CaptureSpan(span.clone())));
+        in_flight += 1;
+
+        let sender = sender.clone();
+        let config = config.clone();
+        let worker_span = info_span!(
+            parent: &span,
+            "capture_worker",
+            window_id = field::Empty,
+            window_title = field::Empty,
+            duration_ms = field::Empty,
+            error = field::Empty,
+        );
+
+        let (mode, priority) = (config.mode, config.worker_priority);
+        let work = move || {
+            let _enter = worker_span.enter();
+            let start = Instant::now();
+            let result = match &compare_job {
+                None => match &job.until_changed {
+                    Some(until) => change::capture_until_changed(&job, until, &config),
+                    None => change::capture_frame(&job, &config),
+                }
+                .and_then(|pixels| match (job.pixel, job.analysis) {
+                    (Some(position), _) => sample_pixel(&pixels.image, position)
+                        .map(|color| CaptureOutput::Pixel { position, color }),
+                    (None, Some(analysis)) => Ok(CaptureOutput::Analysis {
+                        size: pixels.image.dimensions().into(),
+                        result: analysis::analyze(&pixels.image, analysis),
+                    }),
+                    (None, None) => Ok(CaptureOutput::Image(pixels)),
+                }),
+                Some(other) => capture_pair(&job, other, &config).map(CaptureOutput::Diff),
+            };
+            worker_span.record("duration_ms", start.elapsed().as_secs_f64() * 1000.0);
+            if let Err(e) = &result {
+                worker_span.record("error", field::display(e));
+            }
+            sender.send((screenshot_entity, result), config.backpressure);
+        };
+        match mode {
+            CaptureMode::Background => {
+                static NEXT_WORKER: AtomicU64 = AtomicU64::new(0);
+                let name = format!(
+                    "bevy_xcap-capture-{}",
+                    NEXT_WORKER.fetch_add(1, Ordering::Relaxed)
+                );
+                let spawned = std::thread::Builder::new().name(name).spawn(move || {
+                    set_current_thread_priority(priority);
+                    work();
+                });
+                if let Err(e) = spawned {
+                    let error = CaptureError::Spawn(e.to_string());
+                    failure_log.failed(screenshot.target, &error, Instant::now());
+                    commands
+                        .entity(screenshot_entity)
+                        .remove::<Capturing>()
+                        .trigger(move |entity| CaptureFailed { entity, error });
+                    retire(&mut commands, screenshot_entity, screenshot.reusable);
+                    in_flight -= 1;
+                }
+            }
+            CaptureMode::Immediate => work(),
+        }
+    }
+}
+
+/// Fails a request that never reached a worker.
+fn reject(
+    commands: &mut Commands,
+    failure_log: &mut FailureLog,
+    entity: Entity,
+    screenshot: &NativeScreenshot,
+    error: CaptureError,
+) {
+    failure_log.failed(screenshot.target, &error, Instant::now());
+    commands
+        .entity(entity)
+        .trigger(move |entity| CaptureFailed { entity, error });
+    retire(commands, entity, screenshot.reusable);
+}
+
+#[cfg(target_os = "linux")]
+fn set_current_thread_priority(priority: WorkerPriority) {
+    if priority == WorkerPriority::Low {
+        // Linux applies nice values per thread.
+        // SAFETY: plain syscalls on the calling thread.
+        unsafe {
+            libc::setpriority(libc::PRIO_PROCESS, libc::gettid() as libc::id_t, 10);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn set_current_thread_priority(priority: WorkerPriority) {
+    const THREAD_PRIORITY_BELOW_NORMAL: i32 = -1;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> isize;
+        fn SetThreadPriority(thread: isize, priority: i32) -> i32;
+    }
+
+    if priority == WorkerPriority::Low {
+        // SAFETY: GetCurrentThread returns a pseudo-handle that is always
+        // valid for the calling thread.
+        unsafe {
+            SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_BELOW_NORMAL);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn set_current_thread_priority(_priority: WorkerPriority) {}
+
+/// Resolves a window entity into a worker job, or warns and fails with
+/// [`CaptureError::NoNativeHandle`] if it isn't a native window (or, with the
+/// mock backend, not a window).
+fn prepare_job(
+    target: CaptureTarget,
+    screenshot: &NativeScreenshot,
+    handles: &Query<&RawHandleWrapper>,
+    windows: &Query<&Window>,
+    mock: Option<UVec2>,
+) -> Result<CaptureJob, CaptureError> {
+    let target = match target {
+        CaptureTarget::Window(entity) => entity,
+        CaptureTarget::WindowId(id) => {
+            return Ok(CaptureJob {
+                backend: match mock {
+                    Some(size) => JobBackend::Mock(size),
+                    None => JobBackend::XcapId(id),
+                },
+                title: None,
+                client_size: None,
+                decorations: screenshot.decorations,
+                color_space: screenshot.color_space,
+                force_dimensions: screenshot.force_dimensions,
+                max_dimension: screenshot.max_dimension,
+                region: screenshot.region,
+                until_changed: screenshot.until_changed.clone(),
+                stable: screenshot.stable,
+                analysis: screenshot.analysis,
+                pixel: screenshot.pixel,
+            });
+        }
+    };
+    let backend = match (mock, handles.get(target)) {
+        (Some(size), _) if windows.contains(target) => JobBackend::Mock(size),
+        (None, Ok(raw_handle)) => JobBackend::Xcap(raw_handle.clone()),
+        _ => return Err(CaptureError::NoNativeHandle(target)),
+    };
+
+    let window = windows.get(target).ok();
+    Ok(CaptureJob {
+        backend,
+        title: window.map(|w| w.title.clone()),
+        client_size: window.map(|w| w.resolution.physical_size()),
+        decorations: screenshot.decorations,
+        color_space: screenshot.color_space,
+        force_dimensions: screenshot.force_dimensions,
+        max_dimension: screenshot.max_dimension,
+        region: screenshot.region,
+        until_changed: screenshot.until_changed.clone(),
+        stable: screenshot.stable,
+        analysis: screenshot.analysis,
+        pixel: screenshot.pixel,
+    })
+}
+
+/// Physical-pixel rect of a laid-out UI node, if it has a non-empty layout.
+#[cfg(feature = "ui")]
+fn ui_node_rect(
+    node: Entity,
+    nodes: &Query<(&bevy::ui::ComputedNode, &bevy::ui::UiGlobalTransform)>,
+) -> Option<URect> {
+    let (computed, transform) = nodes.get(node).ok()?;
+    if computed.size.x <= 0.0 || computed.size.y <= 0.0 {
+        return None;
+    }
+    let rect = Rect::from_center_size(transform.translation, computed.size);
+    Some(URect::from_corners(
+        rect.min.max(Vec2::ZERO).round().as_uvec2(),
+        rect.max.max(Vec2::ZERO).round().as_uvec2(),
+    ))
+}
+
+/// Captures and post-processes a single window.
+fn run_capture(job: &CaptureJob, config: &CaptureConfig) -> Result<CapturedPixels, CaptureError> {
+    let (image, source, warnings) = match &job.backend {
+        JobBackend::Xcap(raw_handle) => {
+            let handle = raw_handle.get_window_handle();
+            let (mut image, source) = capture_window(handle, job, config)?;
+            let warnings = check_layered_window(&mut image, handle, job);
+            (apply_decorations(image, handle, job), source, warnings)
+        }
+        &JobBackend::XcapId(id) => {
+            let (image, source) = capture_window_id(id, config)?;
+            (image, source, Vec::new())
+        }
+        JobBackend::Mock(size) => {
+            let image = match oversize_limit(*size, config)? {
+                Some(max_pixels) => fit_pixels(checkerboard(*size), max_pixels, config),
+                None => checkerboard(*size),
+            };
+            (image, mock_source(job), Vec::new())
+        }
+    };
+    let image = match job.region {
+        Some(region) => crop(image, region)?,
+        None => image,
+    };
+    let image = match job.max_dimension {
+        Some(max) => limit_dimension(image, max.get(), config),
+        None => image,
+    };
+    let image = match job.force_dimensions {
+        Some(size) => fit_exact(image, size, config),
+        None => image,
+    };
+    let (image, color_space) = apply_color_space(image, job.color_space);
+    Ok(CapturedPixels {
+        image,
+        color_space,
+        source,
+        warnings,
+    })
+}
+
+fn sample_pixel(image: &image::RgbaImage, position: IVec2) -> Result<[u8; 4], CaptureError> {
+    let x = u32::try_from(position.x).ok();
+    let y = u32::try_from(position.y).ok();
+    x.zip(y)
+        .and_then(|(x, y)| image.get_pixel_checked(x, y))
+        .map(|pixel| pixel.0)
+        .ok_or(CaptureError::PixelOutOfBounds {
+            position,
+            size: image.dimensions().into(),
+        })
+}
+
+/// Captures two windows concurrently and diffs them.
+fn capture_pair(
+    a: &CaptureJob,
+    b: &CaptureJob,
+    config: &CaptureConfig,
+) -> Result<ImageDiff, CaptureError> {
+    let (a, b) = std::thread::scope(|scope| {
+        let b = scope.spawn(|| change::capture_frame(b, config));
+        let a = change::capture_frame(a, config);
+        (a, b.join().expect("capture thread panicked"))
+    });
+    diff_images(&a?.image, &b?.image)
+}
+
+fn diff_images(a: &image::RgbaImage, b: &image::RgbaImage) -> Result<ImageDiff, CaptureError> {
+    if a.dimensions() != b.dimensions() {
+        return Err(CaptureError::SizeMismatch {
+            a: a.dimensions().into(),
+            b: b.dimensions().into(),
+        });
+    }
+
+    let mut total = 0u64;
+    let mut max = 0u8;
+    let mut image = image::RgbaImage::new(a.width(), a.height());
+    for ((out, pa), pb) in image.pixels_mut().zip(a.pixels()).zip(b.pixels()) {
+        for c in 0..3 {
+            let d = pa.0[c].abs_diff(pb.0[c]);
+            out.0[c] = d;
+            total += u64::from(d);
+            max = max.max(d);
+        }
+        out.0[3] = u8::MAX;
+    }
+
+    let channels = u64::from(a.width()) * u64::from(a.height()) * 3;
+    let mean = if channels == 0 {
+        0.0
+    } else {
+        total as f32 / channels as f32
+    };
+    Ok(ImageDiff { image, mean, max })
+}
+
+/// Collects completed captures and triggers entity events.
+#[allow(clippy::too_many_arguments)]
+fn poll_captures(
+    mut commands: Commands,
+    receiver: Res<CaptureReceiver>,
+    spans: Query<&CaptureSpan>,
+    capturing: Query<(), With<Capturing>>,
+    screenshots: Query<&NativeScreenshot>,
+    mut history: Option<ResMut<CaptureHistory>>,
+    mut metrics: ResMut<CaptureMetrics>,
+    mut results: ResMut<CaptureResults>,
+    mut failure_log: ResMut<FailureLog>,
+    config: Res<CaptureConfig>,
+) {
+    failure_log.flush(Instant::now());
+
+    for screenshot_entity in receiver.dropped.lock().unwrap().drain(..) {
+        metrics.dropped_results += 1;
+        if !capturing.contains(screenshot_entity) {
+            continue;
+        }
+        warn!("[bevy_xcap] Dropped result for {screenshot_entity:?}: capture channel full");
+        let reusable = screenshots
+            .get(screenshot_entity)
+            .is_ok_and(|screenshot| screenshot.reusable);
+        commands
+            .entity(screenshot_entity)
+            .remove::<Capturing>()
+            .trigger(|entity| CaptureFailed {
+                entity,
+                error: CaptureError::Dropped,
+            });
+        retire(&mut commands, screenshot_entity, reusable);
+    }
+
+    let rx = receiver.rx.lock().unwrap();
+    while let Ok((screenshot_entity, result)) = rx.try_recv() {
+        // Cancelled or despawned while the worker was running.
+        if !capturing.contains(screenshot_entity) {
+            debug!("[bevy_xcap] Discarding result for {screenshot_entity:?}");
+            continue;
+        }
+
+        let span = match spans.get(screenshot_entity) {
+            Ok(root) => info_span!(parent: &root.0, "deliver"),
+            Err(_) => info_span!("deliver", screenshot = ?screenshot_entity),
+        };
+        let _enter = span.enter();
+        let reusable = screenshots
+            .get(screenshot_entity)
+            .is_ok_and(|screenshot| screenshot.reusable);
+
+        match result {
+            Ok(CaptureOutput::Image(pixels)) => {
+                let width = pixels.image.width();
+                let height = pixels.image.height();
+                let color_space = pixels.color_space;
+                let source = pixels.source;
+                let warnings = pixels.warnings;
+                let mut rgba = pixels.image.into_raw();
+                let target = screenshots.get(screenshot_entity).map(|s| s.target);
+                let delivery = screenshots
+                    .get(screenshot_entity)
+                    .ok()
+                    .and_then(|screenshot| screenshot.delivery)
+                    .unwrap_or(config.delivery);
+                let tag = screenshots
+                    .get(screenshot_entity)
+                    .ok()
+                    .and_then(|screenshot| screenshot.tag.clone());
+                if let (Some(history), Ok(target)) = (history.as_deref_mut(), target) {
+                    history.push(
+                        target,
+                        HistoryFrame {
+                            width,
+                            height,
+                            rgba: rgba.clone(),
+                            color_space,
+                            captured_at: Instant::now(),
+                        },
+                    );
+                }
+                let kind = target.map_or(CaptureKind::Window, CaptureTarget::kind);
+                if let (DeliveryMode::Poll | DeliveryMode::Both, Ok(target)) = (delivery, target) {
+                    results.0.push(CapturedImageData {
+                        entity: screenshot_entity,
+                        target,
+                        width,
+                        height,
+                        rgba: if delivery == DeliveryMode::Both {
+                            rgba.clone()
+                        } else {
+                            std::mem::take(&mut rgba)
+                        },
+                        color_space,
+                        bit_depth: CaptureBitDepth::Eight,
+                        kind,
+                        source: source.clone(),
+                        warnings: warnings.clone(),
+                        tag: tag.clone(),
+                    });
+                }
+                let mut entity = commands.entity(screenshot_entity);
+                entity.remove::<Capturing>().insert(Captured);
+                if delivery != DeliveryMode::Poll {
+                    entity.trigger(move |entity| NativeScreenshotCaptured {
+                        entity,
+                        width,
+                        height,
+                        rgba,
+                        color_space,
+                        bit_depth: CaptureBitDepth::Eight,
+                        kind,
+                        source,
+                        warnings,
+                        tag,
+                    });
+                }
+                retire(&mut commands, screenshot_entity, reusable);
+            }
+            Ok(CaptureOutput::Diff(diff)) => {
+                let (width, height) = diff.image.dimensions();
+                let diff_image = diff.image.into_raw();
+                commands
+                    .entity(screenshot_entity)
+                    .remove::<Capturing>()
+                    .insert(Captured)
+                    .trigger(move |entity| WindowDiff {
+                        entity,
+                        width,
+                        height,
+                        diff_image,
+                        mean_diff: diff.mean,
+                        max_diff: diff.max,
+                    });
+                retire(&mut commands, screenshot_entity, reusable);
+            }
+            Ok(CaptureOutput::Analysis { size, result }) => {
+                commands
+                    .entity(screenshot_entity)
+                    .remove::<Capturing>()
+                    .insert(Captured)
+                    .trigger(move |entity| CaptureAnalysis {
+                        entity,
+                        width: size.x,
+                        height: size.y,
+                        result,
+                    });
+                retire(&mut commands, screenshot_entity, reusable);
+            }
+            Ok(CaptureOutput::Pixel { position, color }) => {
+                commands
+                    .entity(screenshot_entity)
+                    .remove::<Capturing>()
+                    .insert(Captured)
+                    .trigger(move |entity| PixelSampled {
+                        entity,
+                        position,
+                        color,
+                    });
+                retire(&mut commands, screenshot_entity, reusable);
+            }
+            Err(error) => {
+                match screenshots.get(screenshot_entity) {
+                    Ok(screenshot) => failure_log.failed(screenshot.target, &error, Instant::now()),
+                    Err(_) => warn!("[bevy_xcap] Failed to capture window: {error}"),
+                }
+                commands
+                    .entity(screenshot_entity)
+                    .remove::<Capturing>()
+                    .trigger(move |entity| CaptureFailed { entity, error });
+                retire(&mut commands, screenshot_entity, reusable);
+            }
+        }
+    }
+}
+
+/// Despawns a finished request, or parks a reusable one as [`Captured`]
+/// until it's re-armed.
+fn retire(commands: &mut Commands, entity: Entity, reusable: bool) {
+    if reusable {
+        commands
+            .entity(entity)
+            .remove::<(Capturing, AwaitingFocus)>()
+            .insert(Captured);
+    } else {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn capture_window(
+    handle: raw_window_handle::RawWindowHandle,
+    job: &CaptureJob,
+    config: &CaptureConfig,
+) -> Result<(image::RgbaImage, CaptureSource), CaptureError> {
+    let all_windows = enumerate_windows(config)?;
+    let target_id = native_window_id(handle);
+
+    // Match by native window ID (Windows/Linux)
+    if let Some(target_id) = target_id {
+        if let Some(w) = all_windows.iter().find(|w| w.id().ok() == Some(target_id)) {
+            return capture_xcap_window(w, MatchKind::WindowId, config);
+        }
+    }
+
+    // Fallback: match by title (macOS doesn't expose window IDs via raw handles)
+    if let Some(title) = job.title.as_deref() {
+        let matches: Vec<&xcap::Window> = all_windows
+            .iter()
+            .filter(|w| w.title().ok().as_deref() == Some(title))
+            .collect();
+
+        if matches.len() > 1 {
+            let candidates: Vec<String> = matches
+                .iter()
+                .map(|w| {
+                    format!(
+                        "{} ({})",
+                        w.id().map_or_else(|_| "?".to_string(), |id| id.to_string()),
+                        w.app_name().unwrap_or_default()
+                    )
+                })
+                .collect();
+            debug!(
+                "[bevy_xcap] {} windows are titled {title:?}: {}",
+                matches.len(),
+                candidates.join(", ")
+            );
+
+            if config.ambiguous_title == AmbiguousTitlePolicy::Error {
+                return Err(CaptureError::AmbiguousTitle {
+                    title: title.to_string(),
+                    ids: matches.iter().filter_map(|w| w.id().ok()).collect(),
+                });
+            }
+        }
+
+        let picked = match config.title_match {
+            TitleMatch::FirstFound => matches.first(),
+            // max_by_key keeps the last maximum; reverse so ties go to the
+            // first found.
+            TitleMatch::TopMost => matches
+                .iter()
+                .rev()
+                .max_by_key(|w| w.z().unwrap_or(i32::MIN)),
+        };
+        if let Some(w) = picked {
+            return capture_xcap_window(w, MatchKind::Title, config);
+        }
+    }
+
+    if config.debug_matching {
+        log_match_failure(target_id, job.title.as_deref(), &all_windows);
+    }
+    Err(CaptureError::WindowNotFound)
+}
+
+fn capture_window_id(
+    id: u32,
+    config: &CaptureConfig,
+) -> Result<(image::RgbaImage, CaptureSource), CaptureError> {
+    let all_windows = enumerate_windows(config)?;
+    match all_windows.iter().find(|w| w.id().ok() == Some(id)) {
+        Some(window) => capture_xcap_window(window, MatchKind::WindowId, config),
+        None => {
+            if config.debug_matching {
+                log_match_failure(Some(id), None, &all_windows);
+            }
+            Err(CaptureError::WindowNotFound)
+        }
+    }
+}
+
+/// [`CaptureConfig::debug_matching`] output.
+fn log_match_failure(id: Option<u32>, title: Option<&str>, windows: &[xcap::Window]) {
+    let listing: Vec<String> = windows
+        .iter()
+        .map(|w| {
+            format!(
+                "  {} {:?} ({})",
+                w.id().map_or_else(|_| "?".to_string(), |id| id.to_string()),
+                w.title().unwrap_or_default(),
+                w.app_name().unwrap_or_default()
+            )
+        })
+        .collect();
+    info!(
+        "[bevy_xcap] No window matched id {id:?} / title {title:?}; {} enumerated:\n{}",
+        windows.len(),
+        listing.join("\n")
+    );
+}
+
+/// Lists the OS windows, retrying an empty list per
+/// [`CaptureConfig::no_windows_retry`].
+fn enumerate_windows(config: &CaptureConfig) -> Result<Vec<xcap::Window>, CaptureError> {
+    let mut retry = 0;
+    loop {
+        let windows = xcap::Window::all().map_err(|e| CaptureError::Enumeration(e.to_string()))?;
+        if !windows.is_empty() {
+            return Ok(windows);
+        }
+
+        match config.no_windows_retry {
+            Some(policy) if retry < policy.max_retries => {
+                let delay = policy.delay(retry);
+                debug!("[bevy_xcap] No windows enumerated, retrying in {delay:?}");
+                std::thread::sleep(delay);
+                retry += 1;
+            }
+            _ => return Err(CaptureError::NoWindowsAvailable),
+        }
+    }
+}
+
+/// Describes the matched window and records it on the current
+/// `capture_worker` span.
+fn resolve_source(window: &xcap::Window, matched_by: MatchKind) -> CaptureSource {
+    let source = CaptureSource {
+        id: window.id().ok(),
+        title: window.title().ok(),
+        app_name: window.app_name().ok(),
+        matched_by,
+    };
+
+    let span = Span::current();
+    if let Some(id) = source.id {
+        span.record("window_id", id);
+    }
+    if let Some(title) = &source.title {
+        span.record("window_title", title.as_str());
+    }
+    source
+}
+
+fn capture_xcap_window(
+    window: &xcap::Window,
+    matched_by: MatchKind,
+    config: &CaptureConfig,
+) -> Result<(image::RgbaImage, CaptureSource), CaptureError> {
+    let source = resolve_source(window, matched_by);
+    let size = match (window.width(), window.height()) {
+        (Ok(width), Ok(height)) => Some(UVec2::new(width, height)),
+        _ => None,
+    };
+    let limit = match size {
+        Some(size) => oversize_limit(size, config)?,
+        None => None,
+    };
+
+    let image = window
+        .capture_image()
+        .map_err(|e| CaptureError::Capture(e.to_string()))?;
+    let image = match limit {
+        Some(max_pixels) => fit_pixels(image, max_pixels, config),
+        None => image,
+    };
+    Ok((image, source))
+}
+
+/// Checks a window of `size` against [`CaptureConfig::max_pixels`] before
+/// capturing it: the pixel budget to downscale to if it's over, or
+/// [`CaptureError::TooLarge`] under [`OversizePolicy::Refuse`].
+fn oversize_limit(size: UVec2, config: &CaptureConfig) -> Result<Option<usize>, CaptureError> {
+    let Some(max_pixels) = config.max_pixels else {
+        return Ok(None);
+    };
+    if size.x as usize * size.y as usize <= max_pixels {
+        return Ok(None);
+    }
+    if config.oversize == OversizePolicy::Refuse {
+        debug!(
+            "[bevy_xcap] Refusing to capture {}x{} window: over {max_pixels} pixels",
+            size.x, size.y
+        );
+        return Err(CaptureError::TooLarge { size, max_pixels });
+    }
+    debug!(
+        "[bevy_xcap] {}x{} window is over {max_pixels} pixels; downscaling the capture",
+        size.x, size.y
+    );
+    Ok(Some(max_pixels))
+}
+
+/// Downscales `image`, preserving aspect ratio, to at most `max_pixels`.
+fn fit_pixels(
+    image: image::RgbaImage,
+    max_pixels: usize,
+    config: &CaptureConfig,
+) -> image::RgbaImage {
+    let (width, height) = image.dimensions();
+    let pixels = width as usize * height as usize;
+    if pixels <= max_pixels {
+        return image;
+    }
+    let scale = (max_pixels as f64 / pixels as f64).sqrt();
+    let width = ((width as f64 * scale).floor() as u32).max(1);
+    let height = ((height as f64 * scale).floor() as u32).max(1);
+    image::imageops::resize(&image, width, height, config.resize_filter)
+}
+
+fn apply_decorations(
+    image: image::RgbaImage,
+    handle: raw_window_handle::RawWindowHandle,
+    job: &CaptureJob,
+) -> image::RgbaImage {
+    if job.decorations == DecorationMode::Full {
+        return image;
+    }
+    let Some(client) = job.client_size else {
+        return image;
+    };
+
+    let frame = UVec2::new(image.width(), image.height());
+    if frame == client {
+        return image;
+    }
+    let rect = win32_client_rect(handle, frame).or_else(|| client_rect(frame, client));
+    let Some(rect) = rect else {
+        warn_once!(
+            "[bevy_xcap] DecorationMode::ClientOnly can't locate the client area on this platform; capturing the full window"
+        );
+        return image;
+    };
+
+    image::imageops::crop_imm(&image, rect.min.x, rect.min.y, rect.width(), rect.height())
+        .to_image()
+}
+
+fn crop(image: image::RgbaImage, region: URect) -> Result<image::RgbaImage, CaptureError> {
+    let size = UVec2::new(image.width(), image.height());
+    let clipped = region.intersect(URect::from_corners(UVec2::ZERO, size));
+    if clipped.is_empty() {
+        return Err(CaptureError::RegionOutOfBounds { region, size });
+    }
+    let crop_size = clipped.size();
+    Ok(
+        image::imageops::crop_imm(&image, clipped.min.x, clipped.min.y, crop_size.x, crop_size.y)
+            .to_image(),
+    )
+}
+
+/// Downscales `image` so its longer side is at most `max`.
+fn limit_dimension(image: image::RgbaImage, max: u32, config: &CaptureConfig) -> image::RgbaImage {
+    let (width, height) = image.dimensions();
+    let longest = width.max(height);
+    if longest <= max {
+        return image;
+    }
+    let scale = max as f32 / longest as f32;
+    let width = ((width as f32 * scale).round() as u32).clamp(1, max);
+    let height = ((height as f32 * scale).round() as u32).clamp(1, max);
+    image::imageops::resize(&image, width, height, config.resize_filter)
+}
+
+/// Downscales `image` to fit inside `size` if needed, then pads it to exactly
+/// `size`.
+pub(crate) fn fit_exact(
+    image: image::RgbaImage,
+    size: UVec2,
+    config: &CaptureConfig,
+) -> image::RgbaImage {
+    if image.dimensions() == (size.x, size.y) {
+        return image;
+    }
+
+    let image = if image.width() > size.x || image.height() > size.y {
+        let scale =
+            (size.x as f32 / image.width() as f32).min(size.y as f32 / image.height() as f32);
+        let width = ((image.width() as f32 * scale).round() as u32).clamp(1, size.x.max(1));
+        let height = ((image.height() as f32 * scale).round() as u32).clamp(1, size.y.max(1));
+        image::imageops::resize(&image, width, height, config.resize_filter)
+    } else {
+        image
+    };
+
+    let mut out = image::RgbaImage::from_pixel(size.x, size.y, image::Rgba(config.pad_color));
+    image::imageops::replace(&mut out, &image, 0, 0);
+    out
+}
+
+fn apply_color_space(
+    mut image: image::RgbaImage,
+    handling: ColorSpaceHandling,
+) -> (image::RgbaImage, CaptureColorSpace) {
+    if handling == ColorSpaceHandling::AsSrgb {
+        return (image, CaptureColorSpace::Srgb);
+    }
+
+    let lut: [u8; 256] = std::array::from_fn(|i| {
+        let c = i as f32 / 255.0;
+        let linear = if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        };
+        (linear * 255.0).round() as u8
+    });
+    for pixel in image.pixels_mut() {
+        for channel in &mut pixel.0[..3] {
+            *channel = lut[*channel as usize];
+        }
+    }
+
+    (image, CaptureColorSpace::Linear)
+}
+
+/// Guesses where the client area sits inside a captured window frame from the
+/// two sizes alone: it assumes a title bar on top and borders of equal width
+/// on the other three sides, which also covers macOS's borderless frames.
+/// Toolbars merged into the title bar, asymmetric borders or a frame that
+/// includes a drop shadow all throw it off. Windows only falls back to it
+/// when `win32_client_rect` can't be used.
+fn client_rect(frame: UVec2, client: UVec2) -> Option<URect> {
+    if client.x > frame.x || client.y > frame.y {
+        return None;
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    {
+        let border = (frame.x - client.x) / 2;
+        let top = (frame.y - client.y).checked_sub(border)?;
+        Some(URect::from_corners(
+            UVec2::new(border, top),
+            UVec2::new(border, top) + client,
+        ))
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    None
+}
+
+/// Locates the client area from the HWND: its screen origin
+/// (`ClientToScreen`) relative to `GetWindowRect`, sized by `GetClientRect`.
+/// `None` if the window is gone or its rect doesn't match the captured
+/// `frame`, since the insets would then be relative to something else.
+#[cfg(target_os = "windows")]
+fn win32_client_rect(handle: raw_window_handle::RawWindowHandle, frame: UVec2) -> Option<URect> {
+    #[repr(C)]
+    #[derive(Default)]
+    struct Rect {
+        left: i32,
+        top: i32,
+        right: i32,
+        bottom: i32,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetWindowRect(hwnd: isize, rect: *mut Rect) -> i32;
+        fn GetClientRect(hwnd: isize, rect: *mut Rect) -> i32;
+        fn ClientToScreen(hwnd: isize, point: *mut Point) -> i32;
+    }
+
+    let raw_window_handle::RawWindowHandle::Win32(h) = handle else {
+        return None;
+    };
+    let hwnd = h.hwnd.get();
+    let (mut window, mut client, mut origin) = (Rect::default(), Rect::default(), Point::default());
+    // SAFETY: the out-pointers are valid locals, and all three calls fail
+    // with 0 for an invalid HWND.
+    let found = unsafe {
+        GetWindowRect(hwnd, &mut window) != 0
+            && GetClientRect(hwnd, &mut client) != 0
+            && ClientToScreen(hwnd, &mut origin) != 0
+    };
+    if !found {
+        return None;
+    }
+
+    let size = |a: i32, b: i32| u32::try_from(b - a).ok();
+    let window_size = UVec2::new(
+        size(window.left, window.right)?,
+        size(window.top, window.bottom)?,
+    );
+    if window_size != frame {
+        return None;
+    }
+    let min = UVec2::new(size(window.left, origin.x)?, size(window.top, origin.y)?);
+    let max = min + UVec2::new(size(0, client.right)?, size(0, client.bottom)?);
+    (max.x <= frame.x && max.y <= frame.y).then(|| URect::from_corners(min, max))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn win32_client_rect(_handle: raw_window_handle::RawWindowHandle, _frame: UVec2) -> Option<URect> {
+    None
+}
+
+/// 8px black and white squares, starting white in the top-left corner.
+fn checkerboard(size: UVec2) -> image::RgbaImage {
+    image::RgbaImage::from_fn(size.x, size.y, |x, y| {
+        let value = if (x / 8 + y / 8) % 2 == 0 { 255 } else { 0 };
+        image::Rgba([value, value, value, 255])
+    })
+}
+
+fn mock_source(job: &CaptureJob) -> CaptureSource {
+    CaptureSource {
+        id: None,
+        title: job.title.clone(),
+        app_name: None,
+        matched_by: MatchKind::Mock,
+    }
+}
+
+/// Flags layered windows, whose GDI capture can come back with zeroed alpha.
+/// A capture that is fully transparent is forced opaque, since a visible
+/// window can't really be invisible everywhere.
+fn check_layered_window(
+    image: &mut image::RgbaImage,
+    handle: raw_window_handle::RawWindowHandle,
+    job: &CaptureJob,
+) -> Vec<CaptureWarning> {
+    if !is_layered_window(handle) {
+        return Vec::new();
+    }
+    let alpha_fixed = image.pixels().all(|p| p[3] == 0);
+    if alpha_fixed {
+        for pixel in image.pixels_mut() {
+            pixel[3] = 255;
+        }
+    }
+    warn_once!(
+        "[bevy_xcap] Window {:?} is layered (WS_EX_LAYERED); captures may have black \
+         regions or wrong alpha",
+        job.title
+    );
+    vec![CaptureWarning::LayeredWindow { alpha_fixed }]
+}
+
+#[cfg(target_os = "windows")]
+fn is_layered_window(handle: raw_window_handle::RawWindowHandle) -> bool {
+    const GWL_EXSTYLE: i32 = -20;
+    const WS_EX_LAYERED: u32 = 0x0008_0000;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetWindowLongW(hwnd: isize, index: i32) -> i32;
+    }
+
+    let raw_window_handle::RawWindowHandle::Win32(h) = handle else {
+        return false;
+    };
+    // SAFETY: GetWindowLongW only reads window data and returns 0 for an
+    // invalid HWND.
+    let ex_style = unsafe { GetWindowLongW(h.hwnd.get(), GWL_EXSTYLE) } as u32;
+    ex_style & WS_EX_LAYERED != 0
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_layered_window(_handle: raw_window_handle::RawWindowHandle) -> bool {
+    false
+}
+
+fn native_window_id(handle: raw_window_handle::RawWindowHandle) -> Option<u32> {
+    #[cfg(target_os = "windows")]
+    if let raw_window_handle::RawWindowHandle::Win32(h) = handle {
+        return Some(h.hwnd.get() as u32);
+    }
+
+    #[cfg(target_os = "linux")]
+    match handle {
+        raw_window_handle::RawWindowHandle::Xlib(h) => return Some(h.window as u32),
+        raw_window_handle::RawWindowHandle::Xcb(h) => return Some(h.window.get()),
+        _ => {}
+    }
+
+    let _ = handle;
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn crop_clips_to_the_image() {
+        let image = crop(solid(10, 10, 0), URect::new(5, 5, 20, 20)).unwrap();
+        assert_eq!(image.dimensions(), (5, 5));
+
+        let result = crop(solid(10, 10, 0), URect::new(10, 10, 20, 20));
+        assert!(matches!(
+            result,
+            Err(CaptureError::RegionOutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn client_rect_rejects_oversized_clients() {
+        assert_eq!(client_rect(UVec2::new(100, 100), UVec2::new(120, 80)), None);
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    #[test]
+    fn client_rect_skips_title_bar_and_borders() {
+        let rect = client_rect(UVec2::new(104, 132), UVec2::new(100, 100)).unwrap();
+        assert_eq!(rect, URect::new(2, 30, 102, 130));
+    }
+
+    fn solid(width: u32, height: u32, value: u8) -> image::RgbaImage {
+        image::RgbaImage::from_pixel(width, height, image::Rgba([value, value, value, 255]))
+    }
+
+    #[test]
+    fn diff_images_reports_mean_and_max() {
+        let a = solid(2, 2, 10);
+        let mut b = solid(2, 2, 10);
+        b.put_pixel(0, 0, image::Rgba([40, 10, 10, 0]));
+
+        let diff = diff_images(&a, &b).unwrap();
+        assert_eq!(diff.max, 30);
+        assert_eq!(diff.mean, 30.0 / 12.0);
+        assert_eq!(diff.image.get_pixel(0, 0).0, [30, 0, 0, 255]);
+        assert_eq!(diff.image.get_pixel(1, 1).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn diff_images_rejects_mismatched_sizes() {
+        let result = diff_images(&solid(2, 2, 0), &solid(3, 2, 0));
+        assert!(matches!(result, Err(CaptureError::SizeMismatch { .. })));
+    }
+
+    #[test]
+    fn limit_dimension_keeps_aspect_ratio() {
+        let config = CaptureConfig::default();
+        let image = limit_dimension(solid(400, 100, 0), 200, &config);
+        assert_eq!(image.dimensions(), (200, 50));
+
+        let image = limit_dimension(solid(1000, 1, 0), 10, &config);
+        assert_eq!(image.dimensions(), (10, 1));
+
+        let image = limit_dimension(solid(50, 20, 0), 200, &config);
+        assert_eq!(image.dimensions(), (50, 20));
+    }
+
+    #[test]
+    fn fit_exact_downscales_then_pads() {
+        let config = CaptureConfig {
+            pad_color: [1, 2, 3, 4],
+            ..default()
+        };
+        let image = fit_exact(solid(200, 100, 9), UVec2::new(50, 50), &config);
+        assert_eq!(image.dimensions(), (50, 50));
+        assert_eq!(image.get_pixel(0, 0).0, [9, 9, 9, 255]);
+        assert_eq!(image.get_pixel(0, 49).0, [1, 2, 3, 4]);
+
+        let image = fit_exact(solid(10, 10, 9), UVec2::new(20, 15), &config);
+        assert_eq!(image.dimensions(), (20, 15));
+        assert_eq!(image.get_pixel(9, 9).0, [9, 9, 9, 255]);
+        assert_eq!(image.get_pixel(19, 14).0, [1, 2, 3, 4]);
+    }
+
+    /// A capture event as the pipeline would trigger it, for tests of the
+    /// observer helpers.
+    pub(crate) fn captured(width: u32, height: u32, rgba: Vec<u8>) -> NativeScreenshotCaptured {
+        NativeScreenshotCaptured {
+            entity: Entity::PLACEHOLDER,
+            width,
+            height,
+            rgba,
+            color_space: CaptureColorSpace::Srgb,
+            bit_depth: CaptureBitDepth::Eight,
+            kind: CaptureKind::Window,
+            source: CaptureSource {
+                id: None,
+                title: None,
+                app_name: None,
+                matched_by: MatchKind::Title,
+            },
+            warnings: Vec::new(),
+            tag: None,
+        }
+    }
+
+    #[test]
+    fn parse_size_accepts_width_x_height() {
+        assert_eq!(parse_size("320x240"), Some(UVec2::new(320, 240)));
+        assert_eq!(parse_size(" 8 x 4 "), Some(UVec2::new(8, 4)));
+        assert_eq!(parse_size("0x240"), None);
+        assert_eq!(parse_size("320"), None);
+        assert_eq!(parse_size("axb"), None);
+    }
+
+    /// What the observers of [`spawn_recorded`] screenshots saw.
+    #[derive(Resource, Default)]
+    pub(crate) struct Outcomes {
+        pub(crate) captured: Vec<(Entity, u32, u32, Vec<u8>)>,
+        pub(crate) failed: Vec<(Entity, CaptureError)>,
+    }
+
+    impl Outcomes {
+        pub(crate) fn len(&self) -> usize {
+            self.captured.len() + self.failed.len()
+        }
+    }
+
+    /// A headless app running the capture pipeline in
+    /// [`CaptureMode::Immediate`], against the mock backend if `mock` is set.
+    pub(crate) fn test_app(mock: Option<UVec2>) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(XCapPlugin)
+            .init_resource::<Outcomes>();
+        config(&mut app).mode = CaptureMode::Immediate;
+        if let Some(size) = mock {
+            app.insert_resource(MockBackend(size));
+        }
+        app
+    }
+
+    pub(crate) fn config(app: &mut App) -> Mut<'_, CaptureConfig> {
+        app.world_mut().resource_mut::<CaptureConfig>()
+    }
+
+    pub(crate) fn spawn_recorded(app: &mut App, screenshot: NativeScreenshot) -> Entity {
+        app.world_mut()
+            .spawn(screenshot)
+            .observe(
+                |captured: On<NativeScreenshotCaptured>, mut outcomes: ResMut<Outcomes>| {
+                    let c = &*captured;
+                    outcomes
+                        .captured
+                        .push((c.entity, c.width, c.height, c.rgba.clone()));
+                },
+            )
+            .observe(
+                |failed: On<CaptureFailed>, mut outcomes: ResMut<Outcomes>| {
+                    outcomes.failed.push((failed.entity, failed.error.clone()));
+                },
+            )
+            .id()
+    }
+
+    /// Updates `app` until `done` holds, giving worker threads time to
+    /// report back.
+    pub(crate) fn run_until(app: &mut App, mut done: impl FnMut(&World) -> bool) {
+        for _ in 0..1000 {
+            app.update();
+            if done(app.world()) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("pipeline didn't settle within 1000 updates");
+    }
+
+    fn outcomes(app: &App) -> &Outcomes {
+        app.world().resource::<Outcomes>()
+    }
+
+    fn settled(count: usize) -> impl FnMut(&World) -> bool {
+        move |world| world.resource::<Outcomes>().len() >= count
+    }
+
+    #[test]
+    fn immediate_capture_lands_in_the_same_update() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        let screenshot = spawn_recorded(&mut app, NativeScreenshot::window(window));
+        app.update();
+
+        assert_eq!(outcomes(&app).captured.len(), 1);
+        assert!(app.world().get_entity(screenshot).is_err());
+    }
+
+    #[test]
+    fn mock_capture_delivers_a_checkerboard() {
+        let mut app = test_app(Some(UVec2::new(16, 8)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        let screenshot = spawn_recorded(&mut app, NativeScreenshot::window(window));
+        run_until(&mut app, settled(1));
+
+        let outcomes = outcomes(&app);
+        assert!(outcomes.failed.is_empty());
+        let [(entity, width, height, rgba)] = outcomes.captured.as_slice() else {
+            panic!("expected one capture, got {}", outcomes.captured.len());
+        };
+        assert_eq!(*entity, screenshot);
+        assert_eq!((*width, *height), (16, 8));
+        assert_eq!(
+            rgba.as_slice(),
+            checkerboard(UVec2::new(16, 8)).as_raw().as_slice()
+        );
+        assert!(app.world().get_entity(screenshot).is_err());
+    }
+
+    #[test]
+    fn mock_capture_reports_source_and_color_space() {
+        #[derive(Resource, Default)]
+        struct Seen(Vec<(CaptureSource, CaptureColorSpace, CaptureKind)>);
+
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        app.init_resource::<Seen>();
+        let window = app
+            .world_mut()
+            .spawn(Window {
+                title: "Editor".to_string(),
+                ..default()
+            })
+            .id();
+        let screenshot = spawn_recorded(
+            &mut app,
+            NativeScreenshot::window(window).color_space(ColorSpaceHandling::ConvertToLinear),
+        );
+        app.world_mut().entity_mut(screenshot).observe(
+            |captured: On<NativeScreenshotCaptured>, mut seen: ResMut<Seen>| {
+                seen.0
+                    .push((captured.source.clone(), captured.color_space, captured.kind));
+            },
+        );
+        run_until(&mut app, settled(1));
+
+        let [(source, color_space, kind)] = app.world().resource::<Seen>().0.as_slice() else {
+            panic!("expected one capture");
+        };
+        assert_eq!(source.matched_by, MatchKind::Mock);
+        assert_eq!(source.title.as_deref(), Some("Editor"));
+        assert_eq!(*color_space, CaptureColorSpace::Linear);
+        assert_eq!(*kind, CaptureKind::Window);
+    }
+
+    #[test]
+    fn convert_to_linear_leaves_alpha_alone() {
+        let (image, color_space) =
+            apply_color_space(solid(1, 1, 128), ColorSpaceHandling::ConvertToLinear);
+        assert_eq!(color_space, CaptureColorSpace::Linear);
+        assert_eq!(image.get_pixel(0, 0).0, [55, 55, 55, 255]);
+
+        let (image, color_space) = apply_color_space(solid(1, 1, 128), ColorSpaceHandling::AsSrgb);
+        assert_eq!(color_space, CaptureColorSpace::Srgb);
+        assert_eq!(image.get_pixel(0, 0).0, [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn full_result_channel_drops_the_newest_results() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(CaptureConfig {
+                result_capacity: 1,
+                backpressure: Backpressure::DropNewest,
+                ..default()
+            })
+            .add_plugins(XCapPlugin)
+            .init_resource::<Outcomes>()
+            .insert_resource(MockBackend(UVec2::new(8, 8)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        for _ in 0..3 {
+            spawn_recorded(&mut app, NativeScreenshot::window(window));
+        }
+        // Let every worker finish before anything drains the channel.
+        app.world_mut().run_system_once(dispatch_captures).unwrap();
+        let dropped = app.world().resource::<CaptureReceiver>().dropped.clone();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while dropped.lock().unwrap().len() < 2 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        run_until(&mut app, settled(3));
+
+        let outcomes = outcomes(&app);
+        assert_eq!(outcomes.captured.len(), 1);
+        assert!(
+            outcomes
+                .failed
+                .iter()
+                .all(|(_, error)| *error == CaptureError::Dropped)
+        );
+        assert_eq!(app.world().resource::<CaptureMetrics>().dropped_results, 2);
+    }
+
+    #[test]
+    fn window_without_native_handle_fails() {
+        let mut app = test_app(None);
+        let window = app.world_mut().spawn(Window::default()).id();
+        spawn_recorded(&mut app, NativeScreenshot::window(window));
+        run_until(&mut app, settled(1));
+
+        let outcomes = outcomes(&app);
+        assert!(outcomes.captured.is_empty());
+        assert!(matches!(
+            outcomes.failed.as_slice(),
+            [(_, CaptureError::NoNativeHandle(entity))] if *entity == window
+        ));
+    }
+
+    #[test]
+    fn cooldown_rejects_a_second_request_for_the_same_target() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        config(&mut app).min_interval = Some(Duration::from_secs(3600));
+        let window = app.world_mut().spawn(Window::default()).id();
+        spawn_recorded(&mut app, NativeScreenshot::window(window));
+        spawn_recorded(&mut app, NativeScreenshot::window(window));
+        run_until(&mut app, settled(2));
+
+        let outcomes = outcomes(&app);
+        assert_eq!(outcomes.captured.len(), 1);
+        assert!(matches!(
+            outcomes.failed.as_slice(),
+            [(_, CaptureError::Cooldown(_))]
+        ));
+    }
+
+    #[test]
+    fn bypass_cooldown_dispatches_within_the_interval() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        config(&mut app).min_interval = Some(Duration::from_secs(3600));
+        let window = app.world_mut().spawn(Window::default()).id();
+        spawn_recorded(&mut app, NativeScreenshot::window(window));
+        spawn_recorded(
+            &mut app,
+            NativeScreenshot::window(window).bypass_cooldown(true),
+        );
+        run_until(&mut app, settled(2));
+
+        let outcomes = outcomes(&app);
+        assert_eq!(outcomes.captured.len(), 2);
+        assert!(outcomes.failed.is_empty());
+    }
+
+    #[test]
+    fn history_keeps_the_latest_frames_per_target() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        app.insert_resource(CaptureHistory::new(2));
+        let window = app.world_mut().spawn(Window::default()).id();
+        for count in 1..=3 {
+            spawn_recorded(&mut app, NativeScreenshot::window(window));
+            run_until(&mut app, settled(count));
+        }
+
+        let history = app.world().resource::<CaptureHistory>();
+        assert_eq!(history.frames(window).count(), 2);
+        assert!(history.frames(window).all(|frame| frame.width == 8));
+    }
+
+    #[test]
+    fn cancel_all_captures_discards_in_flight_results() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        config(&mut app).mode = CaptureMode::Background;
+        let screenshot = spawn_recorded(&mut app, NativeScreenshot::window(window));
+        app.world_mut().run_system_once(dispatch_captures).unwrap();
+        assert!(app.world().entity(screenshot).contains::<Capturing>());
+
+        app.world_mut().commands().cancel_all_captures();
+        app.world_mut().flush();
+        for _ in 0..20 {
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(app.world().get_entity(screenshot).is_err());
+        assert_eq!(outcomes(&app).len(), 0);
+    }
+
+    #[test]
+    fn unfocused_target_times_out() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        config(&mut app).focus_timeout = Duration::ZERO;
+        let window = app
+            .world_mut()
+            .spawn(Window {
+                focused: false,
+                ..default()
+            })
+            .id();
+        spawn_recorded(
+            &mut app,
+            NativeScreenshot::window(window).require_focus(true),
+        );
+        run_until(&mut app, settled(1));
+
+        assert!(matches!(
+            outcomes(&app).failed.as_slice(),
+            [(_, CaptureError::NotFocused)]
+        ));
+    }
+
+    #[test]
+    fn higher_priority_dispatches_first_under_a_concurrency_cap() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        config(&mut app).max_concurrent = Some(1);
+        let window = app.world_mut().spawn(Window::default()).id();
+        let low = spawn_recorded(&mut app, NativeScreenshot::window(window));
+        let high = spawn_recorded(&mut app, NativeScreenshot::window(window).priority(1));
+        run_until(&mut app, settled(2));
+
+        let order: Vec<_> = outcomes(&app).captured.iter().map(|c| c.0).collect();
+        assert_eq!(order, [high, low]);
+    }
+
+    #[test]
+    fn reusable_screenshot_captures_again_after_recapture() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        let screenshot = spawn_recorded(&mut app, NativeScreenshot::window(window).reusable(true));
+        run_until(&mut app, settled(1));
+        assert!(app.world().entity(screenshot).contains::<Captured>());
+
+        app.world_mut().commands().entity(screenshot).recapture();
+        app.world_mut().flush();
+        run_until(&mut app, settled(2));
+        assert_eq!(outcomes(&app).captured.len(), 2);
+        assert!(app.world().entity(screenshot).contains::<Captured>());
+    }
+
+    #[test]
+    fn region_crops_the_capture() {
+        let mut app = test_app(Some(UVec2::new(32, 32)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        let region = URect::new(8, 0, 24, 8);
+        spawn_recorded(&mut app, NativeScreenshot::window(window).region(region));
+        spawn_recorded(
+            &mut app,
+            NativeScreenshot::window(window).region(URect::new(40, 40, 50, 50)),
+        );
+        run_until(&mut app, settled(2));
+
+        let outcomes = outcomes(&app);
+        let [(_, width, height, rgba)] = outcomes.captured.as_slice() else {
+            panic!("expected one capture, got {}", outcomes.captured.len());
+        };
+        assert_eq!((*width, *height), (16, 8));
+        let expected = crop(checkerboard(UVec2::new(32, 32)), region).unwrap();
+        assert_eq!(rgba.as_slice(), expected.as_raw().as_slice());
+        assert!(matches!(
+            outcomes.failed.as_slice(),
+            [(_, CaptureError::RegionOutOfBounds { .. })]
+        ));
+    }
+
+    #[test]
+    fn poll_delivery_queues_results_unless_overridden() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        config(&mut app).delivery = DeliveryMode::Poll;
+        let window = app.world_mut().spawn(Window::default()).id();
+        let polled = spawn_recorded(&mut app, NativeScreenshot::window(window));
+        let observed = spawn_recorded(
+            &mut app,
+            NativeScreenshot::window(window).delivery(DeliveryMode::Observers),
+        );
+        run_until(&mut app, |world| {
+            world.resource::<CaptureResults>().len() + world.resource::<Outcomes>().len() >= 2
+        });
+
+        let results = app.world_mut().resource_mut::<CaptureResults>().drain();
+        let [result] = results.as_slice() else {
+            panic!("expected one polled result, got {}", results.len());
+        };
+        assert_eq!((result.entity, result.target), (polled, window.into()));
+        assert_eq!(result.rgba.len(), 8 * 8 * 4);
+        let captured: Vec<_> = outcomes(&app).captured.iter().map(|c| c.0).collect();
+        assert_eq!(captured, [observed]);
+    }
+
+    #[test]
+    fn window_id_captures_without_a_window_entity() {
+        let mut app = test_app(Some(UVec2::new(8, 4)));
+        spawn_recorded(
+            &mut app,
+            NativeScreenshot::window_id(7).delivery(DeliveryMode::Poll),
+        );
+        run_until(&mut app, |world| {
+            !world.resource::<CaptureResults>().is_empty()
+        });
+
+        let results = app.world_mut().resource_mut::<CaptureResults>().drain();
+        let [result] = results.as_slice() else {
+            panic!("expected one result, got {}", results.len());
+        };
+        assert_eq!(result.target, CaptureTarget::WindowId(7));
+        assert_eq!(result.kind, CaptureKind::External);
+        assert_eq!((result.width, result.height), (8, 4));
+    }
+
+    #[test]
+    fn max_dimension_downscales_and_zero_means_no_limit() {
+        let unset = NativeScreenshot::window(Entity::PLACEHOLDER).max_dimension(0);
+        assert_eq!(unset.max_dimension, None);
+
+        let mut app = test_app(Some(UVec2::new(64, 32)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        let limited = spawn_recorded(&mut app, NativeScreenshot::window(window).max_dimension(16));
+        let unlimited = spawn_recorded(&mut app, NativeScreenshot::window(window).max_dimension(0));
+        run_until(&mut app, settled(2));
+
+        let sizes: HashMap<Entity, (u32, u32)> = outcomes(&app)
+            .captured
+            .iter()
+            .map(|&(entity, width, height, _)| (entity, (width, height)))
+            .collect();
+        assert_eq!(sizes[&limited], (16, 8));
+        assert_eq!(sizes[&unlimited], (64, 32));
+    }
+
+    #[test]
+    fn max_pixels_refuses_or_downscales_oversized_windows() {
+        let mut app = test_app(Some(UVec2::new(40, 10)));
+        config(&mut app).max_pixels = Some(100);
+        let window = app.world_mut().spawn(Window::default()).id();
+        spawn_recorded(&mut app, NativeScreenshot::window(window));
+        run_until(&mut app, settled(1));
+        assert!(matches!(
+            outcomes(&app).failed.as_slice(),
+            [(_, CaptureError::TooLarge { size, max_pixels: 100 })] if *size == UVec2::new(40, 10)
+        ));
+
+        config(&mut app).oversize = OversizePolicy::Downscale;
+        spawn_recorded(&mut app, NativeScreenshot::window(window));
+        run_until(&mut app, settled(2));
+        let (_, width, height, _) = outcomes(&app).captured[0];
+        assert_eq!((width, height), (20, 5));
+    }
+
+    #[test]
+    fn to_rgba_image_checks_the_buffer_length() {
+        let image = captured(2, 1, vec![1, 2, 3, 4, 5, 6, 7, 8])
+            .to_rgba_image()
+            .unwrap();
+        assert_eq!(image.get_pixel(1, 0).0, [5, 6, 7, 8]);
+        assert!(captured(2, 2, vec![0; 8]).to_rgba_image().is_none());
+    }
+
+    #[test]
+    fn tag_is_echoed_to_observers_and_results() {
+        #[derive(Resource, Default)]
+        struct Tags(Vec<Option<String>>);
+
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        app.init_resource::<Tags>();
+        let window = app.world_mut().spawn(Window::default()).id();
+        app.world_mut()
+            .spawn(
+                NativeScreenshot::window(window)
+                    .tag("request-7")
+                    .delivery(DeliveryMode::Both),
+            )
+            .observe(
+                |captured: On<NativeScreenshotCaptured>, mut tags: ResMut<Tags>| {
+                    tags.0.push(captured.tag.clone());
+                },
+            );
+        app.update();
+
+        let tag = Some("request-7".to_string());
+        let results = app.world_mut().resource_mut::<CaptureResults>().drain();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].tag, tag);
+        assert_eq!(app.world().resource::<Tags>().0, [tag]);
+    }
+
+    #[test]
+    fn until_changed_delivers_a_change_or_times_out() {
+        let mut app = test_app(Some(UVec2::new(16, 16)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        let same = ChangeBaseline::Pixels {
+            image: Arc::new(checkerboard(UVec2::new(16, 16))),
+            min_mean_diff: 0.0,
+        };
+        let black = ChangeBaseline::Pixels {
+            image: Arc::new(image::RgbaImage::new(16, 16)),
+            min_mean_diff: 0.0,
+        };
+        let timeout = Duration::from_millis(20);
+        let unchanged = spawn_recorded(
+            &mut app,
+            NativeScreenshot::window(window).until_changed(same, timeout),
+        );
+        let changed = spawn_recorded(
+            &mut app,
+            NativeScreenshot::window(window).until_changed(black, timeout),
+        );
+        run_until(&mut app, settled(2));
+
+        let outcomes = outcomes(&app);
+        assert_eq!(outcomes.captured.len(), 1);
+        assert_eq!(outcomes.captured[0].0, changed);
+        assert_eq!(
+            outcomes.failed,
+            [(unchanged, CaptureError::Timeout(timeout))]
+        );
+    }
+
+    #[test]
+    fn analyze_delivers_statistics_instead_of_pixels() {
+        #[derive(Resource, Default)]
+        struct Results(Vec<(u32, u32, AnalysisResult)>);
+
+        let mut app = test_app(Some(UVec2::new(16, 16)));
+        app.init_resource::<Results>();
+        let window = app.world_mut().spawn(Window::default()).id();
+        for (region, analysis) in [
+            (Some(URect::new(0, 0, 8, 8)), Analysis::AverageColor),
+            (None, Analysis::Histogram(2)),
+        ] {
+            let screenshot = spawn_recorded(
+                &mut app,
+                NativeScreenshot::window(window).analyze(region, analysis),
+            );
+            app.world_mut().entity_mut(screenshot).observe(
+                |analyzed: On<CaptureAnalysis>, mut results: ResMut<Results>| {
+                    let a = &*analyzed;
+                    results.0.push((a.width, a.height, a.result.clone()));
+                },
+            );
+        }
+        app.update();
+
+        assert!(outcomes(&app).captured.is_empty());
+        let halves = vec![128, 128];
+        let histogram = [halves.clone(), halves.clone(), halves, vec![0, 256]];
+        assert_eq!(
+            app.world().resource::<Results>().0,
+            [
+                (8, 8, AnalysisResult::AverageColor([255; 4])),
+                (16, 16, AnalysisResult::Histogram(histogram)),
+            ]
+        );
+    }
+
+    #[test]
+    fn stable_retakes_until_two_captures_agree() {
+        #[derive(Resource, Default)]
+        struct Warnings(Vec<(Entity, Vec<CaptureWarning>)>);
+
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        app.init_resource::<Warnings>();
+        config(&mut app).stable_retry = RetryPolicy {
+            max_retries: 1,
+            backoff: Duration::ZERO,
+        };
+        let window = app.world_mut().spawn(Window::default()).id();
+        let spawn_stable = |app: &mut App| {
+            let entity = spawn_recorded(app, NativeScreenshot::window(window).stable(true));
+            app.world_mut().entity_mut(entity).observe(
+                |captured: On<NativeScreenshotCaptured>, mut warnings: ResMut<Warnings>| {
+                    warnings
+                        .0
+                        .push((captured.entity, captured.warnings.clone()));
+                },
+            );
+            entity
+        };
+        let agreeing = spawn_stable(&mut app);
+        run_until(&mut app, settled(1));
+        // No two captures of the mock can differ by less than nothing.
+        config(&mut app).stable_threshold = -1.0;
+        let torn = spawn_stable(&mut app);
+        run_until(&mut app, settled(2));
+
+        assert_eq!(outcomes(&app).captured.len(), 2);
+        assert_eq!(
+            app.world().resource::<Warnings>().0,
+            [
+                (agreeing, Vec::new()),
+                (torn, vec![CaptureWarning::Unstable { attempts: 3 }]),
+            ]
+        );
+    }
+
+    #[test]
+    fn failure_log_counts_repeats_within_the_interval() {
+        let target = CaptureTarget::WindowId(1);
+        let error = CaptureError::Capture("boom".to_string());
+        let start = Instant::now();
+        let mut log = FailureLog::default();
+
+        log.failed(target, &error, start);
+        log.failed(target, &error, start + Duration::from_millis(10));
+        log.failed(target, &error, start + Duration::from_millis(20));
+        assert_eq!(log.0[&target].repeats, 2);
+
+        let other = CaptureError::Capture("other".to_string());
+        log.failed(target, &other, start + Duration::from_millis(30));
+        assert_eq!(log.0[&target].repeats, 0);
+        assert_eq!(log.0[&target].error, other);
+    }
+
+    #[test]
+    fn failure_log_flush_forgets_recovered_targets() {
+        let (quiet, noisy) = (CaptureTarget::WindowId(1), CaptureTarget::WindowId(2));
+        let error = CaptureError::Capture("boom".to_string());
+        let start = Instant::now();
+        let mut log = FailureLog::default();
+        log.failed(quiet, &error, start);
+        log.failed(noisy, &error, start);
+        log.failed(noisy, &error, start + Duration::from_millis(10));
+
+        log.flush(start + Duration::from_millis(500));
+        assert_eq!(log.0.len(), 2);
+
+        let later = start + FAILURE_LOG_INTERVAL;
+        log.flush(later);
+        assert!(!log.0.contains_key(&quiet));
+        assert_eq!(log.0[&noisy].repeats, 0);
+        assert_eq!(log.0[&noisy].logged_at, later);
+    }
+
+    #[test]
+    fn pixel_samples_one_color_or_fails_out_of_bounds() {
+        #[derive(Resource, Default)]
+        struct Samples(Vec<(IVec2, [u8; 4])>);
+
+        let mut app = test_app(Some(UVec2::new(16, 16)));
+        app.init_resource::<Samples>();
+        let window = app.world_mut().spawn(Window::default()).id();
+        let mut outside = Entity::PLACEHOLDER;
+        for position in [IVec2::new(0, 0), IVec2::new(8, 0), IVec2::new(16, 0)] {
+            outside = spawn_recorded(&mut app, NativeScreenshot::pixel(window, position));
+            app.world_mut().entity_mut(outside).observe(
+                |sampled: On<PixelSampled>, mut samples: ResMut<Samples>| {
+                    samples.0.push((sampled.position, sampled.color));
+                },
+            );
+        }
+        app.update();
+
+        let outcomes = outcomes(&app);
+        assert!(outcomes.captured.is_empty());
+        assert_eq!(
+            outcomes.failed,
+            [(
+                outside,
+                CaptureError::PixelOutOfBounds {
+                    position: IVec2::new(16, 0),
+                    size: UVec2::new(16, 16),
+                }
+            )]
+        );
+        assert_eq!(
+            app.world().resource::<Samples>().0,
+            [
+                (IVec2::new(0, 0), [255; 4]),
+                (IVec2::new(8, 0), [0, 0, 0, 255]),
+            ]
+        );
+    }
 }
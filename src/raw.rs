@@ -0,0 +1,298 @@
+//! Uncompressed captures in memory-mapped files, for exports too large to
+//! encode comfortably.
+//!
+//! The file is a 16-byte header followed by the pixels, row-major RGBA8:
+//!
+//! | offset | size | field                                  |
+//! |--------|------|----------------------------------------|
+//! | 0      | 4    | magic `BXCR`                           |
+//! | 4      | 4    | width, little-endian `u32`             |
+//! | 8      | 4    | height, little-endian `u32`            |
+//! | 12     | 4    | format: `0` sRGB, `1` linear RGBA8     |
+
+use crate::{
+    CaptureBitDepth, CaptureColorSpace, CaptureError, CaptureKind, CaptureSource, CaptureTarget,
+    CapturedImageData, MatchKind, NativeScreenshotCaptured,
+};
+use bevy::prelude::*;
+use image::ImageFormat;
+use memmap2::{Mmap, MmapMut};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"BXCR";
+const HEADER_LEN: usize = 16;
+
+/// Observer callback that writes captured pixels to `path` in the raw format
+/// above, through a memory map instead of an encoder's buffer.
+pub fn save_raw_mmap(path: impl Into<PathBuf>) -> impl FnMut(On<NativeScreenshotCaptured>) {
+    let path = path.into();
+    move |captured: On<NativeScreenshotCaptured>| {
+        let c = &*captured;
+        if !crate::save::create_parent_dir(&path) {
+            return;
+        }
+        match write_raw(&path, c) {
+            Ok(()) => info!(
+                "[bevy_xcap] Saved {}x{} raw capture to {}",
+                c.width,
+                c.height,
+                path.display()
+            ),
+            Err(e) => error!("[bevy_xcap] Failed to save raw capture: {e}"),
+        }
+    }
+}
+
+fn write_raw(path: &Path, c: &NativeScreenshotCaptured) -> io::Result<()> {
+    if c.rgba.len() != c.width as usize * c.height as usize * 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "capture buffer doesn't match its dimensions",
+        ));
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    file.set_len((HEADER_LEN + c.rgba.len()) as u64)?;
+
+    // SAFETY: the file was just created and sized by us; nothing else is
+    // expected to resize it while it's mapped.
+    let mut map = unsafe { MmapMut::map_mut(&file)? };
+    map[..4].copy_from_slice(MAGIC);
+    map[4..8].copy_from_slice(&c.width.to_le_bytes());
+    map[8..12].copy_from_slice(&c.height.to_le_bytes());
+    map[12..16].copy_from_slice(&format_code(c.color_space).to_le_bytes());
+    map[HEADER_LEN..].copy_from_slice(&c.rgba);
+    map.flush()
+}
+
+fn format_code(color_space: CaptureColorSpace) -> u32 {
+    match color_space {
+        CaptureColorSpace::Srgb => 0,
+        CaptureColorSpace::Linear => 1,
+    }
+}
+
+/// A raw capture mapped from disk by [`load_raw_mmap`]. The pixels are read
+/// lazily by the OS as they're touched.
+pub struct RawCapture {
+    pub width: u32,
+    pub height: u32,
+    pub color_space: CaptureColorSpace,
+    map: Mmap,
+}
+
+impl RawCapture {
+    /// Row-major RGBA8 pixels, `width * height * 4` bytes.
+    pub fn rgba(&self) -> &[u8] {
+        &self.map[HEADER_LEN..]
+    }
+
+    /// Copies the pixels into an [`image::RgbaImage`].
+    pub fn to_rgba_image(&self) -> image::RgbaImage {
+        image::RgbaImage::from_raw(self.width, self.height, self.rgba().to_vec())
+            .expect("length checked on load")
+    }
+}
+
+/// Maps a file written by [`save_raw_mmap`], validating its header and size.
+pub fn load_raw_mmap(path: impl AsRef<Path>) -> io::Result<RawCapture> {
+    let file = File::open(path)?;
+    // SAFETY: the map is read-only; as with any mmap, truncating the file
+    // from another process while it's mapped is undefined behavior.
+    let map = unsafe { Mmap::map(&file)? };
+
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    if map.len() < HEADER_LEN || &map[..4] != MAGIC {
+        return Err(invalid("not a bevy_xcap raw capture"));
+    }
+    let field = |offset: usize| u32::from_le_bytes(map[offset..offset + 4].try_into().unwrap());
+    let (width, height) = (field(4), field(8));
+    let color_space = match field(12) {
+        0 => CaptureColorSpace::Srgb,
+        1 => CaptureColorSpace::Linear,
+        _ => return Err(invalid("unknown raw capture format")),
+    };
+    let len = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4));
+    if len != Some(map.len() - HEADER_LEN) {
+        return Err(invalid("raw capture size doesn't match its header"));
+    }
+
+    Ok(RawCapture {
+        width,
+        height,
+        color_space,
+        map,
+    })
+}
+
+/// Reads a file written by [`save_raw_mmap`] back into memory, e.g. for a
+/// post-run batch step. Nothing ties it to a live request, so `entity` and
+/// `target` are [`Entity::PLACEHOLDER`], `kind` is [`CaptureKind::File`] and
+/// `source` only records [`MatchKind::File`].
+pub fn load_capture(path: impl AsRef<Path>) -> Result<CapturedImageData, CaptureError> {
+    let path = path.as_ref();
+    let raw =
+        load_raw_mmap(path).map_err(|e| CaptureError::Load(format!("{}: {e}", path.display())))?;
+    Ok(CapturedImageData {
+        entity: Entity::PLACEHOLDER,
+        target: CaptureTarget::Window(Entity::PLACEHOLDER),
+        width: raw.width,
+        height: raw.height,
+        rgba: raw.rgba().to_vec(),
+        color_space: raw.color_space,
+        bit_depth: CaptureBitDepth::Eight,
+        kind: CaptureKind::File,
+        source: CaptureSource {
+            id: None,
+            title: None,
+            app_name: None,
+            matched_by: MatchKind::File,
+        },
+        warnings: Vec::new(),
+        tag: None,
+    })
+}
+
+/// Re-encodes the raw capture at `src` into `dst` as `format`, creating
+/// missing parent directories. Pixels are written as stored, so a linear
+/// capture isn't converted back to sRGB.
+pub fn reencode(
+    src: impl AsRef<Path>,
+    dst: impl AsRef<Path>,
+    format: ImageFormat,
+) -> Result<(), CaptureError> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    let raw =
+        load_raw_mmap(src).map_err(|e| CaptureError::Load(format!("{}: {e}", src.display())))?;
+    let bytes = crate::save::encode(raw.to_rgba_image(), format)
+        .map_err(|e| CaptureError::Save(e.to_string()))?;
+
+    if !crate::save::create_parent_dir(dst) {
+        return Err(CaptureError::Save(format!(
+            "can't create the directory for {}",
+            dst.display()
+        )));
+    }
+    std::fs::write(dst, bytes).map_err(|e| CaptureError::Save(format!("{}: {e}", dst.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::captured;
+
+    /// A path in the temp dir that no other test uses.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("bevy_xcap_{}_{name}.raw", std::process::id()))
+    }
+
+    fn gradient(width: u32, height: u32) -> NativeScreenshotCaptured {
+        let mut capture = captured(
+            width,
+            height,
+            (0..width * height * 4).map(|i| i as u8).collect(),
+        );
+        capture.color_space = CaptureColorSpace::Linear;
+        capture
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round_trip");
+        let original = gradient(3, 2);
+        write_raw(&path, &original).unwrap();
+
+        let loaded = load_raw_mmap(&path).unwrap();
+        assert_eq!((loaded.width, loaded.height), (3, 2));
+        assert_eq!(loaded.rgba(), original.rgba.as_slice());
+        assert_eq!(loaded.color_space, CaptureColorSpace::Linear);
+        drop(loaded);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn truncated_file_is_rejected() {
+        let path = temp_path("truncated");
+        write_raw(&path, &gradient(4, 4)).unwrap();
+        let len = std::fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 4)
+            .unwrap();
+
+        let result = load_raw_mmap(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn overflowing_header_is_rejected() {
+        let path = temp_path("overflow");
+        let mut header = MAGIC.to_vec();
+        header.extend(u32::MAX.to_le_bytes());
+        header.extend(u32::MAX.to_le_bytes());
+        header.extend(0u32.to_le_bytes());
+        std::fs::write(&path, header).unwrap();
+
+        let result = load_raw_mmap(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn bad_magic_is_rejected() {
+        let path = temp_path("bad_magic");
+        std::fs::write(&path, [0u8; HEADER_LEN + 4]).unwrap();
+
+        let result = load_raw_mmap(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        let path = temp_path("bad_magic_capture");
+        std::fs::write(&path, [0u8; HEADER_LEN + 4]).unwrap();
+        let result = load_capture(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(CaptureError::Load(_))));
+    }
+
+    #[test]
+    fn load_capture_marks_the_capture_as_a_file() {
+        let path = temp_path("load_capture");
+        let original = gradient(3, 2);
+        write_raw(&path, &original).unwrap();
+
+        let loaded = load_capture(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((loaded.width, loaded.height), (3, 2));
+        assert_eq!(loaded.rgba, original.rgba);
+        assert_eq!(loaded.color_space, CaptureColorSpace::Linear);
+        assert_eq!(loaded.kind, CaptureKind::File);
+        assert_eq!(loaded.source.matched_by, MatchKind::File);
+    }
+
+    #[test]
+    fn reencode_writes_into_missing_directories() {
+        let src = temp_path("reencode");
+        let dir = std::env::temp_dir().join(format!("bevy_xcap_{}_reencoded", std::process::id()));
+        let dst = dir.join("nested/capture.png");
+        let original = gradient(3, 2);
+        write_raw(&src, &original).unwrap();
+
+        let result = reencode(&src, &dst, ImageFormat::Png);
+        let decoded = image::open(&dst).map(|image| image.to_rgba8());
+        std::fs::remove_file(&src).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+        assert_eq!(decoded.unwrap().as_raw(), &original.rgba);
+    }
+}
@@ -0,0 +1,150 @@
+//! State for interactive, drag-to-select region captures.
+
+use crate::{CaptureError, CaptureFailed};
+use bevy::prelude::*;
+
+/// A region being dragged out over a window, started with
+/// [`begin_region_selection`].
+///
+/// Input handling stays in the app: feed it cursor positions, e.g. from
+/// [`Window::cursor_position`], then call [`finish_region_selection`].
+/// Positions are logical pixels from the top-left of the client area, as
+/// Bevy reports them; the conversion to capture pixels happens on finish.
+///
+/// ```ignore
+/// fn drag(
+///     mouse: Res<ButtonInput<MouseButton>>,
+///     windows: Query<&Window>,
+///     mut selection: ResMut<RegionSelection>,
+/// ) {
+///     let Some(cursor) = windows.get(selection.window).ok().and_then(|w| w.cursor_position())
+///     else {
+///         return;
+///     };
+///     if mouse.just_pressed(MouseButton::Left) {
+///         selection.drag_from(cursor);
+///     } else if mouse.pressed(MouseButton::Left) {
+///         selection.drag_to(cursor);
+///     }
+/// }
+/// ```
+///
+/// [`begin_region_selection`]: crate::CaptureCommandsExt::begin_region_selection
+/// [`finish_region_selection`]: crate::CaptureCommandsExt::finish_region_selection
+#[derive(Resource, Clone, Debug)]
+pub struct RegionSelection {
+    pub window: Entity,
+    /// Where the drag started.
+    pub anchor: Option<Vec2>,
+    /// Where the drag is now.
+    pub cursor: Option<Vec2>,
+}
+
+impl RegionSelection {
+    pub fn new(window: Entity) -> Self {
+        Self {
+            window,
+            anchor: None,
+            cursor: None,
+        }
+    }
+
+    /// Starts a new drag at `position`, discarding any previous one.
+    pub fn drag_from(&mut self, position: Vec2) {
+        self.anchor = Some(position);
+        self.cursor = Some(position);
+    }
+
+    pub fn drag_to(&mut self, position: Vec2) {
+        self.cursor = Some(position);
+    }
+
+    /// The selected rect in logical pixels, whichever way it was dragged.
+    pub fn rect(&self) -> Option<Rect> {
+        Some(Rect::from_corners(self.anchor?, self.cursor?))
+    }
+
+    /// The selected rect in physical pixels, as
+    /// [`NativeScreenshot::region`](crate::NativeScreenshot::region) expects.
+    /// `None` if nothing, or an empty area, is selected.
+    pub fn physical_rect(&self, scale_factor: f32) -> Option<URect> {
+        let rect = self.rect()?;
+        let rect = URect::from_corners(
+            (rect.min.max(Vec2::ZERO) * scale_factor).round().as_uvec2(),
+            (rect.max.max(Vec2::ZERO) * scale_factor).round().as_uvec2(),
+        );
+        (!rect.is_empty()).then_some(rect)
+    }
+}
+
+/// Marks a [`finish_region_selection`] entity that had nothing to capture,
+/// so it fails once the caller's observers are attached.
+///
+/// [`finish_region_selection`]: crate::CaptureCommandsExt::finish_region_selection
+#[derive(Component)]
+pub(crate) struct EmptySelection;
+
+pub(crate) fn fail_empty_selections(
+    mut commands: Commands,
+    empty: Query<Entity, With<EmptySelection>>,
+) {
+    for entity in &empty {
+        commands
+            .entity(entity)
+            .trigger(|entity| CaptureFailed {
+                entity,
+                error: CaptureError::NoRegionSelected,
+            })
+            .despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CaptureCommandsExt;
+    use crate::tests::{Outcomes, test_app};
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn physical_rect_scales_and_normalizes_the_drag() {
+        let mut selection = RegionSelection::new(Entity::PLACEHOLDER);
+        assert_eq!(selection.physical_rect(2.0), None);
+
+        selection.drag_from(Vec2::new(30.0, 5.0));
+        selection.drag_to(Vec2::new(10.0, 20.0));
+        assert_eq!(
+            selection.physical_rect(2.0),
+            Some(URect::new(20, 10, 60, 40))
+        );
+
+        selection.drag_to(Vec2::new(30.0, 40.0));
+        assert_eq!(selection.physical_rect(1.0), None);
+    }
+
+    #[test]
+    fn finishing_without_a_selection_fails_on_the_next_update() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        let screenshot = app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| {
+                commands
+                    .finish_region_selection()
+                    .observe(
+                        |failed: On<CaptureFailed>, mut outcomes: ResMut<Outcomes>| {
+                            outcomes.failed.push((failed.entity, failed.error.clone()));
+                        },
+                    )
+                    .id()
+            })
+            .unwrap();
+        assert!(app.world().get_entity(screenshot).is_ok());
+
+        app.update();
+        assert!(app.world().get_entity(screenshot).is_err());
+        assert_eq!(
+            app.world().resource::<Outcomes>().failed,
+            [(screenshot, CaptureError::NoRegionSelected)]
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Native capture compared against Bevy's own wgpu [`Screenshot`], to see
+//! what the renderer doesn't.
+
+use crate::{
+    CaptureConfig, CaptureError, CaptureFailed, DecorationMode, DeliveryMode, NativeScreenshot,
+    NativeScreenshotCaptured, diff_images,
+};
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use std::time::{Duration, Instant};
+
+/// Captures `window` both natively and with Bevy's [`Screenshot`], then
+/// triggers [`NativeVsRenderedDiff`] on this entity and despawns it.
+///
+/// Whatever differs is content the renderer never sees: native toolkit UI,
+/// embedded third-party views, OS overlays. The native capture covers the
+/// client area only and is resized to the rendered image's size when the two
+/// disagree, e.g. if the OS reports the window at a different scale. The
+/// captures can't be synchronized exactly (the rendered one is of the next
+/// frame Bevy presents, the native one of whatever the compositor shows when
+/// the worker gets to it), so animated content shows up as difference too.
+///
+/// The native capture ignores [`CaptureConfig::min_interval`]. If it fails,
+/// its [`CaptureFailed`] is forwarded to this entity, which then despawns. If
+/// either capture hasn't landed within `timeout`, e.g. because Bevy never
+/// presented a frame, [`CaptureFailed`] fires with
+/// [`CaptureError::Timeout`] instead.
+///
+/// ```ignore
+/// commands
+///     .spawn(NativeVsRendered::new(window))
+///     .observe(|diff: On<NativeVsRenderedDiff>| {
+///         info!("Native content differs by {:.1} on average", diff.mean_diff);
+///     });
+/// ```
+#[derive(Component)]
+#[require(RenderedPair)]
+pub struct NativeVsRendered {
+    pub window: Entity,
+    /// How long to wait for both captures. Defaults to 5 s.
+    pub timeout: Duration,
+}
+
+impl NativeVsRendered {
+    pub fn new(window: Entity) -> Self {
+        Self {
+            window,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Triggered on a [`NativeVsRendered`] entity once both captures landed.
+#[derive(EntityEvent)]
+pub struct NativeVsRenderedDiff {
+    pub entity: Entity,
+    /// Size of the rendered image, which the native capture was resized to.
+    pub width: u32,
+    pub height: u32,
+    /// Size of the native capture before resizing.
+    pub native_size: UVec2,
+    /// Per-channel absolute difference of RGB, with alpha forced opaque, as
+    /// in [`WindowDiff::diff_image`](crate::WindowDiff::diff_image).
+    pub diff_image: Vec<u8>,
+    /// Mean absolute difference over all RGB channels, `0.0..=255.0`.
+    pub mean_diff: f32,
+    pub max_diff: u8,
+}
+
+#[derive(Component, Default)]
+pub(crate) struct RenderedPair {
+    started_at: Option<Instant>,
+    native: Option<image::RgbaImage>,
+    rendered: Option<image::RgbaImage>,
+}
+
+pub(crate) fn start_rendered_comparisons(
+    mut commands: Commands,
+    mut comparisons: Query<(Entity, &NativeVsRendered, &mut RenderedPair)>,
+) {
+    let now = Instant::now();
+    for (comparison, request, mut pair) in &mut comparisons {
+        if let Some(started_at) = pair.started_at {
+            if now.duration_since(started_at) >= request.timeout {
+                let error = CaptureError::Timeout(request.timeout);
+                commands
+                    .entity(comparison)
+                    .trigger(move |entity| CaptureFailed { entity, error })
+                    .despawn();
+            }
+            continue;
+        }
+        pair.started_at = Some(now);
+
+        commands.spawn(Screenshot::window(request.window)).observe(
+            move |captured: On<ScreenshotCaptured>,
+                  mut commands: Commands,
+                  mut pairs: Query<&mut RenderedPair>,
+                  config: Res<CaptureConfig>| {
+                let Ok(mut pair) = pairs.get_mut(comparison) else {
+                    return;
+                };
+                match captured.image.clone().try_into_dynamic() {
+                    Ok(image) => pair.rendered = Some(image.to_rgba8()),
+                    Err(e) => {
+                        error!("[bevy_xcap] Can't read Bevy's screenshot for comparison: {e}");
+                        commands.entity(comparison).try_despawn();
+                        return;
+                    }
+                }
+                finish(&mut commands, comparison, &pair, &config);
+            },
+        );
+
+        commands
+            .spawn(
+                NativeScreenshot::window(request.window)
+                    .decorations(DecorationMode::ClientOnly)
+                    .bypass_cooldown(true)
+                    .delivery(DeliveryMode::Observers),
+            )
+            .observe(
+                move |captured: On<NativeScreenshotCaptured>,
+                      mut commands: Commands,
+                      mut pairs: Query<&mut RenderedPair>,
+                      config: Res<CaptureConfig>| {
+                    let Ok(mut pair) = pairs.get_mut(comparison) else {
+                        return;
+                    };
+                    let Some(image) = captured.to_rgba_image() else {
+                        error!("[bevy_xcap] Capture buffer doesn't match its dimensions");
+                        commands.entity(comparison).try_despawn();
+                        return;
+                    };
+                    pair.native = Some(image);
+                    finish(&mut commands, comparison, &pair, &config);
+                },
+            )
+            .observe(move |failed: On<CaptureFailed>, mut commands: Commands| {
+                let error = failed.error.clone();
+                if let Ok(mut entity) = commands.get_entity(comparison) {
+                    entity
+                        .trigger(move |entity| CaptureFailed { entity, error })
+                        .despawn();
+                }
+            });
+    }
+}
+
+/// Diffs the pair once both halves are in.
+fn finish(
+    commands: &mut Commands,
+    comparison: Entity,
+    pair: &RenderedPair,
+    config: &CaptureConfig,
+) {
+    let (Some(native), Some(rendered)) = (&pair.native, &pair.rendered) else {
+        return;
+    };
+    let native_size = native.dimensions().into();
+    let (width, height) = rendered.dimensions();
+    let resized;
+    let native = if native.dimensions() == (width, height) {
+        native
+    } else {
+        resized = image::imageops::resize(native, width, height, config.resize_filter);
+        &resized
+    };
+    let diff = diff_images(native, rendered).expect("sizes aligned above");
+
+    let diff_image = diff.image.into_raw();
+    commands
+        .entity(comparison)
+        .trigger(move |entity| NativeVsRenderedDiff {
+            entity,
+            width,
+            height,
+            native_size,
+            diff_image,
+            mean_diff: diff.mean,
+            max_diff: diff.max,
+        })
+        .despawn();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{Outcomes, run_until, test_app};
+
+    #[test]
+    fn comparison_times_out_without_a_rendered_frame() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        let window = app.world_mut().spawn(Window::default()).id();
+        let timeout = Duration::from_millis(20);
+        let comparison = app
+            .world_mut()
+            .spawn(NativeVsRendered { window, timeout })
+            .observe(
+                |failed: On<CaptureFailed>, mut outcomes: ResMut<Outcomes>| {
+                    outcomes.failed.push((failed.entity, failed.error.clone()));
+                },
+            )
+            .id();
+        run_until(&mut app, |world| world.get_entity(comparison).is_err());
+
+        // No renderer runs here, so Bevy's screenshot never lands.
+        assert_eq!(
+            app.world().resource::<Outcomes>().failed,
+            [(comparison, CaptureError::Timeout(timeout))]
+        );
+    }
+}
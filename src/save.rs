@@ -0,0 +1,306 @@
+//! Observer callbacks that persist captures.
+
+#[cfg(feature = "sidecar")]
+use crate::NativeScreenshot;
+use crate::NativeScreenshotCaptured;
+use bevy::prelude::*;
+use image::{DynamicImage, ImageBuffer, ImageFormat, Rgba};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+/// Observer callback that saves captured pixels to a PNG file, creating
+/// missing parent directories.
+pub fn save_to_disk(
+    path: impl Into<PathBuf>,
+) -> impl FnMut(On<NativeScreenshotCaptured>) {
+    let path = path.into();
+    move |captured: On<NativeScreenshotCaptured>| {
+        let c = &*captured;
+        if !create_parent_dir(&path) {
+            return;
+        }
+        match image::save_buffer(&path, &c.rgba, c.width, c.height, image::ColorType::Rgba8) {
+            Ok(()) => info!("[bevy_xcap] Saved {}x{} screenshot to {}", c.width, c.height, path.display()),
+            Err(e) => error!("[bevy_xcap] Failed to save screenshot: {e}"),
+        }
+    }
+}
+
+/// Like [`save_to_disk`], but writes a 16-bit-per-channel PNG.
+///
+/// Captures are currently always 8-bit (see
+/// [`CaptureBitDepth`](crate::CaptureBitDepth)), so each channel is expanded
+/// exactly, `v * 257`, rather than gaining precision. Useful when the file
+/// feeds a pipeline that expects 16-bit input.
+pub fn save_to_disk_16bit(path: impl Into<PathBuf>) -> impl FnMut(On<NativeScreenshotCaptured>) {
+    let path = path.into();
+    move |captured: On<NativeScreenshotCaptured>| {
+        let c = &*captured;
+        if !create_parent_dir(&path) {
+            return;
+        }
+        let wide = c.rgba.iter().map(|&v| u16::from(v) * 257).collect();
+        let Some(buffer) = ImageBuffer::<Rgba<u16>, Vec<u16>>::from_raw(c.width, c.height, wide)
+        else {
+            error!("[bevy_xcap] Capture buffer doesn't match its dimensions");
+            return;
+        };
+        match buffer.save_with_format(&path, ImageFormat::Png) {
+            Ok(()) => info!(
+                "[bevy_xcap] Saved {}x{} 16-bit screenshot to {}",
+                c.width,
+                c.height,
+                path.display()
+            ),
+            Err(e) => error!("[bevy_xcap] Failed to save screenshot: {e}"),
+        }
+    }
+}
+
+/// Observer callback that saves captured pixels to a PNG file plus a JSON
+/// sidecar next to it, named by appending `.json` to `path`. Missing parent
+/// directories are created.
+///
+/// The sidecar records the size, the target window's scale factor, a Unix
+/// timestamp in milliseconds, the matched xcap window, and the crate version.
+/// Each write is logged; if the PNG fails the sidecar is skipped.
+#[cfg(feature = "sidecar")]
+pub fn save_with_sidecar(
+    path: impl Into<PathBuf>,
+) -> impl FnMut(On<NativeScreenshotCaptured>, Query<&NativeScreenshot>, Query<&Window>) {
+    let path = path.into();
+    move |captured: On<NativeScreenshotCaptured>,
+          screenshots: Query<&NativeScreenshot>,
+          windows: Query<&Window>| {
+        let c = &*captured;
+        if !create_parent_dir(&path) {
+            return;
+        }
+        let saved =
+            image::save_buffer(&path, &c.rgba, c.width, c.height, image::ColorType::Rgba8);
+        if let Err(e) = saved {
+            error!("[bevy_xcap] Failed to save screenshot: {e}");
+            return;
+        }
+        info!("[bevy_xcap] Saved {}x{} screenshot to {}", c.width, c.height, path.display());
+
+        let scale_factor = screenshots
+            .get(c.entity)
+            .ok()
+            .and_then(|screenshot| windows.get(screenshot.target.entity()?).ok())
+            .map(|window| window.scale_factor());
+        let timestamp_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let metadata = serde_json::json!({
+            "width": c.width,
+            "height": c.height,
+            "scale_factor": scale_factor,
+            "timestamp_ms": timestamp_ms,
+            "source": {
+                "id": c.source.id,
+                "title": c.source.title,
+                "app_name": c.source.app_name,
+            },
+            "crate_version": env!("CARGO_PKG_VERSION"),
+        });
+
+        let mut sidecar = path.clone().into_os_string();
+        sidecar.push(".json");
+        let sidecar = PathBuf::from(sidecar);
+        let written = serde_json::to_vec_pretty(&metadata)
+            .map_err(std::io::Error::from)
+            .and_then(|json| std::fs::write(&sidecar, json));
+        match written {
+            Ok(()) => info!("[bevy_xcap] Saved capture metadata to {}", sidecar.display()),
+            Err(e) => error!("[bevy_xcap] Failed to save capture metadata: {e}"),
+        }
+    }
+}
+
+/// Observer callback that encodes captured pixels as `format` and writes them
+/// to `writer` (a pipe, an in-memory buffer, an upload stream...).
+///
+/// Encoding and writing happen on one background thread that owns the
+/// writer, so an observer that fires several times appends one encoded image
+/// per capture, in capture order. The thread exits once the observer is
+/// dropped. Failures are logged.
+pub fn save_to_writer<W: Write + Send + 'static>(
+    mut writer: W,
+    format: ImageFormat,
+) -> impl FnMut(On<NativeScreenshotCaptured>) {
+    let (sender, receiver) = mpsc::channel::<image::RgbaImage>();
+    std::thread::spawn(move || {
+        for buffer in receiver {
+            let (width, height) = buffer.dimensions();
+            let bytes = match encode(buffer, format) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    error!("[bevy_xcap] Failed to encode screenshot as {format:?}: {e}");
+                    continue;
+                }
+            };
+
+            match writer.write_all(&bytes).and_then(|()| writer.flush()) {
+                Ok(()) => info!(
+                    "[bevy_xcap] Wrote {width}x{height} screenshot ({} bytes)",
+                    bytes.len()
+                ),
+                Err(e) => error!("[bevy_xcap] Failed to write screenshot: {e}"),
+            }
+        }
+    });
+
+    move |captured: On<NativeScreenshotCaptured>| {
+        let Some(buffer) = captured.to_rgba_image() else {
+            error!("[bevy_xcap] Capture buffer doesn't match its dimensions");
+            return;
+        };
+        if sender.send(buffer).is_err() {
+            error!("[bevy_xcap] Screenshot writer thread has stopped");
+        }
+    }
+}
+
+/// Creates the parent directories of `path`, logging and returning `false` on
+/// failure.
+pub(crate) fn create_parent_dir(path: &Path) -> bool {
+    let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) else {
+        return true;
+    };
+    match std::fs::create_dir_all(parent) {
+        Ok(()) => true,
+        Err(e) => {
+            error!("[bevy_xcap] Failed to create {}: {e}", parent.display());
+            false
+        }
+    }
+}
+
+/// Encodes an RGBA buffer in memory, dropping alpha for formats without it.
+pub(crate) fn encode(buffer: image::RgbaImage, format: ImageFormat) -> image::ImageResult<Vec<u8>> {
+    let image = DynamicImage::ImageRgba8(buffer);
+    let image = match format {
+        ImageFormat::Jpeg | ImageFormat::Pnm => {
+            DynamicImage::ImageRgb8(image.to_rgb8())
+        }
+        _ => image,
+    };
+
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, format)?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::captured;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn save_to_writer_appends_in_capture_order() {
+        let buffer = SharedBuffer::default();
+        let mut world = World::new();
+        let screenshot = world
+            .spawn_empty()
+            .observe(save_to_writer(buffer.clone(), ImageFormat::Png))
+            .id();
+        let images: Vec<image::RgbaImage> = (1..=8)
+            .map(|size| image::RgbaImage::from_pixel(size, size, image::Rgba([size as u8; 4])))
+            .collect();
+        for image in &images {
+            let mut event = captured(image.width(), image.height(), image.as_raw().clone());
+            event.entity = screenshot;
+            world.trigger(event);
+        }
+
+        let expected: Vec<u8> = images
+            .into_iter()
+            .flat_map(|image| encode(image, ImageFormat::Png).unwrap())
+            .collect();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while buffer.0.lock().unwrap().len() < expected.len() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(*buffer.0.lock().unwrap(), expected);
+    }
+
+    #[cfg(feature = "sidecar")]
+    #[test]
+    fn save_with_sidecar_records_size_scale_and_source() {
+        let path =
+            std::env::temp_dir().join(format!("bevy_xcap_sidecar_{}.png", std::process::id()));
+        let sidecar = path.with_extension("png.json");
+        let mut world = World::new();
+        let window = world.spawn(Window::default()).id();
+        let screenshot = world
+            .spawn(NativeScreenshot::window(window))
+            .observe(save_with_sidecar(path.clone()))
+            .id();
+        let mut event = captured(2, 1, vec![9; 8]);
+        event.entity = screenshot;
+        event.source.title = Some("Editor".to_string());
+        world.trigger(event);
+
+        let json = std::fs::read(&sidecar);
+        let png = image::open(&path).map(|image| image.to_rgba8());
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&sidecar).unwrap();
+        let metadata: serde_json::Value = serde_json::from_slice(&json.unwrap()).unwrap();
+        assert_eq!(png.unwrap().as_raw(), &vec![9; 8]);
+        assert_eq!(metadata["width"], 2);
+        assert_eq!(metadata["height"], 1);
+        assert_eq!(metadata["scale_factor"], 1.0);
+        assert_eq!(metadata["source"]["title"], "Editor");
+        assert_eq!(metadata["crate_version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn save_to_disk_creates_parent_directories() {
+        let dir = std::env::temp_dir().join(format!("bevy_xcap_save_{}", std::process::id()));
+        let path = dir.join("nested/shot.png");
+        let mut world = World::new();
+        let screenshot = world.spawn_empty().observe(save_to_disk(path.clone())).id();
+        let mut event = captured(2, 2, vec![7; 16]);
+        event.entity = screenshot;
+        world.trigger(event);
+
+        let saved = image::open(&path).map(|image| image.to_rgba8());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(saved.unwrap().as_raw(), &vec![7; 16]);
+    }
+
+    #[test]
+    fn save_to_disk_16bit_expands_each_channel() {
+        let path = std::env::temp_dir().join(format!("bevy_xcap_16bit_{}.png", std::process::id()));
+        let mut world = World::new();
+        let screenshot = world
+            .spawn_empty()
+            .observe(save_to_disk_16bit(path.clone()))
+            .id();
+        let mut event = captured(1, 1, vec![0, 1, 128, 255]);
+        event.entity = screenshot;
+        world.trigger(event);
+
+        let saved = image::open(&path).map(|image| image.to_rgba16());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.unwrap().as_raw(), &[0, 257, 128 * 257, 65535]);
+    }
+}
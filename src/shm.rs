@@ -0,0 +1,186 @@
+//! Captures delivered to another process through shared memory.
+//!
+//! A [`ShmSink`] maps a file, ideally on a RAM-backed filesystem such as
+//! `/dev/shm` on Linux, and overwrites it with every frame: a reader that
+//! falls behind simply sees the latest one. The file is a 32-byte header
+//! followed by room for `capacity` bytes of row-major RGBA8 pixels. All
+//! fields are little-endian:
+//!
+//! | offset | size | field                                          |
+//! |--------|------|------------------------------------------------|
+//! | 0      | 4    | magic `BXCS`                                   |
+//! | 4      | 4    | format: `0` sRGB, `1` linear RGBA8             |
+//! | 8      | 8    | sequence number, `u64`, odd while writing      |
+//! | 16     | 4    | width, `u32`                                   |
+//! | 20     | 4    | height, `u32`                                  |
+//! | 24     | 8    | capacity, `u64`                                |
+//!
+//! The sequence number works as a seqlock. To read a frame, load it until
+//! it's even, copy the format, size and `width * height * 4` pixel bytes,
+//! then load it again: if it changed, a frame was written meanwhile, so
+//! retry. It's 8-byte aligned, so the reader can load it atomically.
+
+use crate::{CaptureColorSpace, NativeScreenshotCaptured};
+use bevy::prelude::*;
+use memmap2::MmapMut;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+use std::sync::{Arc, Mutex};
+
+const MAGIC: &[u8; 4] = b"BXCS";
+const HEADER_LEN: usize = 32;
+const SEQUENCE_OFFSET: usize = 8;
+
+/// Latest-frame shared memory channel to another process, see the
+/// [module docs](self) for the layout. Clones share the same mapping.
+///
+/// ```ignore
+/// let sink = ShmSink::create("/dev/shm/bevy_xcap", UVec2::new(3840, 2160))?;
+/// commands
+///     .spawn(NativeScreenshot::window(window).reusable(true))
+///     .observe(sink.observer());
+/// ```
+#[derive(Clone)]
+pub struct ShmSink {
+    shared: Arc<Mutex<SharedFrame>>,
+}
+
+struct SharedFrame {
+    map: MmapMut,
+    sequence: u64,
+}
+
+impl ShmSink {
+    /// Creates or truncates the file at `path`, sized for frames of up to
+    /// `max_size` pixels. Larger frames are refused by [`write`](Self::write).
+    pub fn create(path: impl AsRef<Path>, max_size: UVec2) -> io::Result<Self> {
+        let capacity = max_size.x as usize * max_size.y as usize * 4;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len((HEADER_LEN + capacity) as u64)?;
+
+        // SAFETY: the file was just created and sized by us; readers map it
+        // too but never resize it.
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        map[..4].copy_from_slice(MAGIC);
+        map[24..32].copy_from_slice(&(capacity as u64).to_le_bytes());
+        map.flush()?;
+        Ok(Self {
+            shared: Arc::new(Mutex::new(SharedFrame { map, sequence: 0 })),
+        })
+    }
+
+    /// Publishes one frame, replacing the previous one whether or not it
+    /// was read.
+    pub fn write(
+        &self,
+        width: u32,
+        height: u32,
+        color_space: CaptureColorSpace,
+        rgba: &[u8],
+    ) -> io::Result<()> {
+        let len = width as usize * height as usize * 4;
+        if rgba.len() != len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "capture buffer doesn't match its dimensions",
+            ));
+        }
+        let mut shared = self.shared.lock().unwrap();
+        if HEADER_LEN + len > shared.map.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{width}x{height} frame exceeds the shared memory capacity"),
+            ));
+        }
+
+        let sequence = shared.sequence;
+        let format: u32 = match color_space {
+            CaptureColorSpace::Srgb => 0,
+            CaptureColorSpace::Linear => 1,
+        };
+        // Everything goes through raw pointers derived from one base pointer:
+        // a `&mut [u8]` over the map while an `&AtomicU64` into it is alive
+        // would be aliasing UB.
+        let base = shared.map.as_mut_ptr();
+        // SAFETY: the map is page-aligned and was checked above to hold the
+        // header and `len` pixel bytes, so every write stays in bounds and the
+        // sequence field is a valid, 8-byte aligned u64. Other processes only
+        // ever access that field atomically, and the mutex keeps writers in
+        // this process apart.
+        unsafe {
+            let atomic = &*(base.add(SEQUENCE_OFFSET) as *const AtomicU64);
+            atomic.store((sequence + 1).to_le(), Ordering::Relaxed);
+            fence(Ordering::Release);
+
+            write_bytes(base, 4, &format.to_le_bytes());
+            write_bytes(base, 16, &width.to_le_bytes());
+            write_bytes(base, 20, &height.to_le_bytes());
+            write_bytes(base, HEADER_LEN, rgba);
+
+            atomic.store((sequence + 2).to_le(), Ordering::Release);
+        }
+        shared.sequence = sequence + 2;
+        Ok(())
+    }
+
+    /// Observer callback that [`write`](Self::write)s every capture. Failures
+    /// are logged.
+    pub fn observer(&self) -> impl FnMut(On<NativeScreenshotCaptured>) {
+        let sink = self.clone();
+        move |captured: On<NativeScreenshotCaptured>| {
+            let c = &*captured;
+            if let Err(e) = sink.write(c.width, c.height, c.color_space, &c.rgba) {
+                error!("[bevy_xcap] Failed to write capture to shared memory: {e}");
+            }
+        }
+    }
+}
+
+/// Copies `bytes` to `offset` bytes past `base`.
+///
+/// # Safety
+///
+/// `base + offset .. base + offset + bytes.len()` must be valid for writes
+/// and not overlap `bytes`.
+unsafe fn write_bytes(base: *mut u8, offset: usize, bytes: &[u8]) {
+    // SAFETY: upheld by the caller.
+    unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), base.add(offset), bytes.len()) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn write_publishes_the_frame_and_bumps_the_sequence() {
+        let path = std::env::temp_dir().join(format!("bevy_xcap_shm_{}", std::process::id()));
+        let sink = ShmSink::create(&path, UVec2::new(2, 2)).unwrap();
+        sink.write(2, 1, CaptureColorSpace::Linear, &[1, 2, 3, 4, 5, 6, 7, 8])
+            .unwrap();
+        let too_large = sink.write(4, 4, CaptureColorSpace::Srgb, &[0; 64]);
+        let mismatched = sink.write(2, 2, CaptureColorSpace::Srgb, &[0; 4]);
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(read_u32(&bytes, 4), 1);
+        assert_eq!(u64::from_le_bytes(bytes[8..16].try_into().unwrap()), 2);
+        assert_eq!((read_u32(&bytes, 16), read_u32(&bytes, 20)), (2, 1));
+        assert_eq!(u64::from_le_bytes(bytes[24..32].try_into().unwrap()), 16);
+        assert_eq!(&bytes[HEADER_LEN..HEADER_LEN + 8], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(too_large.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(mismatched.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
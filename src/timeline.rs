@@ -0,0 +1,153 @@
+//! Captures at scripted points in time.
+
+use crate::{DeliveryMode, NativeScreenshot};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Captures `target` once at each of `times`, measured in real time
+/// ([`Time<Real>`], so `TimePlugin` is required) from the first update after
+/// the timeline is spawned.
+///
+/// Times already past when the timeline starts (e.g. `Duration::ZERO`) fire
+/// right away, in order. Each capture is a regular [`NativeScreenshot`]
+/// carrying a [`ScheduledCapture`]; `on_capture` receives it to attach
+/// observers, which always receive the capture regardless of
+/// [`CaptureConfig::delivery`](crate::CaptureConfig::delivery), and it skips
+/// [`CaptureConfig::min_interval`](crate::CaptureConfig::min_interval) so
+/// closely scheduled times aren't dropped. The timeline despawns itself after
+/// the last capture is requested.
+///
+/// ```ignore
+/// commands.spawn(CaptureTimeline::new(
+///     window,
+///     [1, 2, 5].map(Duration::from_secs),
+///     |at, screenshot| {
+///         screenshot.observe(save_to_disk(format!("demo_{}s.png", at.as_secs())));
+///     },
+/// ));
+/// ```
+#[derive(Component)]
+#[require(TimelineState)]
+pub struct CaptureTimeline {
+    pub target: Entity,
+    /// Sorted on construction.
+    pub times: Vec<Duration>,
+    #[allow(clippy::type_complexity)]
+    pub on_capture: Box<dyn Fn(Duration, &mut EntityCommands) + Send + Sync>,
+}
+
+impl CaptureTimeline {
+    pub fn new(
+        target: Entity,
+        times: impl IntoIterator<Item = Duration>,
+        on_capture: impl Fn(Duration, &mut EntityCommands) + Send + Sync + 'static,
+    ) -> Self {
+        let mut times: Vec<Duration> = times.into_iter().collect();
+        times.sort();
+        Self {
+            target,
+            times,
+            on_capture: Box::new(on_capture),
+        }
+    }
+}
+
+/// On screenshot entities spawned by a [`CaptureTimeline`]; observers can
+/// query it off the entity to learn which point in the script they got.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ScheduledCapture {
+    pub timeline: Entity,
+    /// The scheduled time, relative to the timeline's start.
+    pub at: Duration,
+}
+
+#[derive(Component, Default)]
+pub(crate) struct TimelineState {
+    started: Option<Duration>,
+    next: usize,
+}
+
+pub(crate) fn drive_capture_timelines(
+    mut commands: Commands,
+    time: Option<Res<Time<Real>>>,
+    mut timelines: Query<(Entity, &CaptureTimeline, &mut TimelineState)>,
+) {
+    let Some(now) = time.map(|time| time.elapsed()) else {
+        return;
+    };
+    for (entity, timeline, mut state) in &mut timelines {
+        let elapsed = now - *state.started.get_or_insert(now);
+        while let Some(&at) = timeline.times.get(state.next).filter(|&&at| at <= elapsed) {
+            let mut screenshot = commands.spawn((
+                NativeScreenshot::window(timeline.target)
+                    .delivery(DeliveryMode::Observers)
+                    .bypass_cooldown(true),
+                ScheduledCapture {
+                    timeline: entity,
+                    at,
+                },
+            ));
+            (timeline.on_capture)(at, &mut screenshot);
+            state.next += 1;
+        }
+        if state.next >= timeline.times.len() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{Outcomes, config, run_until, test_app};
+    use crate::{CaptureCommandsExt, NativeScreenshotCaptured};
+
+    fn spawn_timeline(app: &mut App, times: impl IntoIterator<Item = Duration>) -> Entity {
+        let window = app.world_mut().spawn(Window::default()).id();
+        app.world_mut()
+            .spawn(CaptureTimeline::new(window, times, |_, screenshot| {
+                screenshot.observe(
+                    |captured: On<NativeScreenshotCaptured>, mut outcomes: ResMut<Outcomes>| {
+                        let c = &*captured;
+                        outcomes
+                            .captured
+                            .push((c.entity, c.width, c.height, c.rgba.clone()));
+                    },
+                );
+            }))
+            .id()
+    }
+
+    fn captured(count: usize) -> impl FnMut(&World) -> bool {
+        move |world| world.resource::<Outcomes>().captured.len() >= count
+    }
+
+    #[test]
+    fn times_due_together_all_reach_observers() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        config(&mut app).delivery = crate::DeliveryMode::Poll;
+        let timeline = spawn_timeline(&mut app, [Duration::ZERO, Duration::ZERO]);
+        run_until(&mut app, captured(2));
+        assert!(app.world().get_entity(timeline).is_err());
+    }
+
+    #[test]
+    fn scheduled_captures_ignore_the_cooldown() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        config(&mut app).min_interval = Some(Duration::from_secs(3600));
+        spawn_timeline(&mut app, [Duration::ZERO, Duration::ZERO]);
+        run_until(&mut app, captured(2));
+        assert!(app.world().resource::<Outcomes>().failed.is_empty());
+    }
+
+    #[test]
+    fn cancel_all_captures_stops_the_timeline() {
+        let mut app = test_app(Some(UVec2::new(8, 8)));
+        let timeline = spawn_timeline(&mut app, [Duration::ZERO, Duration::from_secs(3600)]);
+        run_until(&mut app, captured(1));
+
+        app.world_mut().commands().cancel_all_captures();
+        app.world_mut().flush();
+        assert!(app.world().get_entity(timeline).is_err());
+    }
+}
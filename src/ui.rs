@@ -0,0 +1,113 @@
+//! Show captures as thumbnails in Bevy UI.
+
+use crate::{
+    CaptureColorSpace, CaptureConfig, CaptureTarget, NativeScreenshot, NativeScreenshotCaptured,
+    fit_exact,
+};
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+
+/// Tags the UI node that [`to_ui_thumbnail`] shows captures of `target` in.
+///
+/// Query for `Added<CaptureThumbnail>` to parent the node into your layout.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CaptureThumbnail {
+    pub target: CaptureTarget,
+}
+
+/// Observer callback that shows the capture in a `size` pixel [`ImageNode`].
+///
+/// The capture is downscaled to fit and padded to exactly `size` with
+/// [`CaptureConfig::pad_color`]. The first capture of a window spawns a root
+/// UI node tagged with [`CaptureThumbnail`]; later captures of the same
+/// window, from new requests or a
+/// [`reusable`](NativeScreenshot::reusable) one, replace that node's image
+/// in place.
+///
+/// The node is a separate entity, so it survives the screenshot entity being
+/// despawned after a one-shot capture and stays around until you despawn it.
+#[allow(clippy::type_complexity)]
+pub fn to_ui_thumbnail(
+    size: UVec2,
+) -> impl FnMut(
+    On<NativeScreenshotCaptured>,
+    Commands,
+    Query<&NativeScreenshot>,
+    Query<(&CaptureThumbnail, &mut ImageNode)>,
+    ResMut<Assets<Image>>,
+    Res<CaptureConfig>,
+) {
+    move |captured: On<NativeScreenshotCaptured>,
+          mut commands: Commands,
+          screenshots: Query<&NativeScreenshot>,
+          mut thumbnails: Query<(&CaptureThumbnail, &mut ImageNode)>,
+          mut images: ResMut<Assets<Image>>,
+          config: Res<CaptureConfig>| {
+        let Ok(screenshot) = screenshots.get(captured.entity) else {
+            return;
+        };
+        let c = &*captured;
+        let Some(buffer) = c.to_rgba_image() else {
+            return;
+        };
+        let image = images.add(Image::from_dynamic(
+            fit_exact(buffer, size, &config).into(),
+            c.color_space == CaptureColorSpace::Srgb,
+            RenderAssetUsages::default(),
+        ));
+
+        if let Some((_, mut node)) = thumbnails
+            .iter_mut()
+            .find(|(thumbnail, _)| thumbnail.target == screenshot.target)
+        {
+            node.image = image;
+            return;
+        }
+        commands.spawn((
+            CaptureThumbnail {
+                target: screenshot.target,
+            },
+            ImageNode::new(image),
+            Node {
+                width: Val::Px(size.x as f32),
+                height: Val::Px(size.y as f32),
+                ..default()
+            },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{Outcomes, run_until, spawn_recorded, test_app};
+
+    #[test]
+    fn later_captures_replace_the_thumbnail_image() {
+        let mut app = test_app(Some(UVec2::new(16, 8)));
+        app.add_plugins(AssetPlugin::default())
+            .init_asset::<Image>();
+        let window = app.world_mut().spawn(Window::default()).id();
+        for count in 1..=2 {
+            let screenshot = spawn_recorded(&mut app, NativeScreenshot::window(window));
+            app.world_mut()
+                .entity_mut(screenshot)
+                .observe(to_ui_thumbnail(UVec2::new(4, 4)));
+            run_until(&mut app, |world| {
+                world.resource::<Outcomes>().len() >= count
+            });
+        }
+
+        let world = app.world_mut();
+        let nodes: Vec<_> = world
+            .query_filtered::<&ImageNode, With<CaptureThumbnail>>()
+            .iter(world)
+            .map(|node| node.image.clone())
+            .collect();
+        let [image] = nodes.as_slice() else {
+            panic!("expected one thumbnail, got {}", nodes.len());
+        };
+        let image = world.resource::<Assets<Image>>().get(image).unwrap();
+        assert_eq!(image.size(), UVec2::new(4, 4));
+    }
+}